@@ -0,0 +1,55 @@
+use rust_decimal::Decimal;
+
+/// Balances of an account trading on `PieOrderBook`
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Account {
+    pub cash: Decimal,
+    /// collateral reserved for resting orders and short positions
+    pub locked: Decimal,
+    /// contracts held per outcome, negative if short
+    pub positions: Vec<Decimal>,
+    /// held contracts per outcome reserved for resting sell orders
+    pub reserved: Vec<Decimal>,
+    /// contracts traded over the lifetime of the account
+    pub volume: Decimal,
+    /// complete sets minted minus complete sets merged. Each set pays out
+    /// contract_price on resolution, as exactly one contract of a set wins.
+    pub sets: Decimal,
+}
+
+impl Account {
+    pub(crate) fn new(outcomes: usize) -> Self {
+        Account {
+            cash: Decimal::ZERO,
+            locked: Decimal::ZERO,
+            positions: vec![Decimal::ZERO; outcomes],
+            reserved: vec![Decimal::ZERO; outcomes],
+            volume: Decimal::ZERO,
+            sets: Decimal::ZERO,
+        }
+    }
+
+    /// Returns cash that is not locked
+    pub fn available(&self) -> Decimal {
+        self.cash.saturating_sub(self.locked)
+    }
+
+    /// Returns collateral needed to cover the worst case payout of the
+    /// positions. Only one outcome can win, so this is the largest short
+    /// position times contract_price.
+    pub(crate) fn margin(&self, contract_price: Decimal) -> Option<Decimal> {
+        let min_position = self
+            .positions
+            .iter()
+            .copied()
+            .min()
+            .unwrap_or(Decimal::ZERO);
+
+        if min_position.is_sign_negative() {
+            min_position.abs().checked_mul(contract_price)
+        } else {
+            Some(Decimal::ZERO)
+        }
+    }
+}
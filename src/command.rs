@@ -0,0 +1,122 @@
+use rust_decimal::Decimal;
+use rust_ob::Side;
+
+use crate::{
+    execution::Execution,
+    fee::FeeSchedule,
+    order::{OrderOptions, StopOrder},
+    rules::TradingRules,
+    settlement::Settlement,
+    state::MarketState,
+};
+
+/// State changing operation of a `PieOrderBook`. Every successful operation
+/// is recorded in the command log, which reconstructs the order book when replayed.
+/// Orders record the time of the order book clock they were processed at.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Command<OrderID, AccountID> {
+    New {
+        contract_price: Decimal,
+        outcomes: usize,
+    },
+    Deposit {
+        account: AccountID,
+        amount: Decimal,
+    },
+    Withdraw {
+        account: AccountID,
+        amount: Decimal,
+    },
+    MintCompleteSet {
+        account: AccountID,
+        quantity: Decimal,
+    },
+    MergeCompleteSet {
+        account: AccountID,
+        quantity: Decimal,
+    },
+    LimitOrder {
+        id: OrderID,
+        outcome: usize,
+        #[cfg_attr(feature = "serde", serde(with = "crate::snapshot::SideDef"))]
+        side: Side,
+        price: Decimal,
+        quantity: Decimal,
+        options: OrderOptions<AccountID>,
+        now: u64,
+    },
+    MarketOrder {
+        id: OrderID,
+        outcome: usize,
+        #[cfg_attr(feature = "serde", serde(with = "crate::snapshot::SideDef"))]
+        side: Side,
+        quantity: Decimal,
+        worst_price: Option<Decimal>,
+        options: OrderOptions<AccountID>,
+        now: u64,
+    },
+    BasketOrder {
+        legs: Vec<(OrderID, usize)>,
+        #[cfg_attr(feature = "serde", serde(with = "crate::snapshot::SideDef"))]
+        side: Side,
+        price: Decimal,
+        quantity: Decimal,
+        options: OrderOptions<AccountID>,
+        now: u64,
+    },
+    CancelOrder {
+        id: OrderID,
+    },
+    AmendOrder {
+        id: OrderID,
+        price: Decimal,
+        quantity: Decimal,
+        now: u64,
+    },
+    SubmitStopOrder {
+        id: OrderID,
+        order: StopOrder<AccountID>,
+        now: u64,
+    },
+    CancelStopOrder {
+        id: OrderID,
+    },
+    ExpireOrders {
+        now: u64,
+    },
+    Resolve {
+        winning_outcome: usize,
+    },
+    ResolvePartial {
+        payouts: Vec<Decimal>,
+    },
+    Void,
+    SetTradingRules {
+        rules: TradingRules,
+    },
+    SetFeeSchedule {
+        schedule: Option<FeeSchedule>,
+    },
+    SetMarketState {
+        state: MarketState,
+    },
+    HaltOutcome {
+        outcome: usize,
+    },
+    ResumeOutcome {
+        outcome: usize,
+    },
+}
+
+/// Result of applying a `Command`
+#[derive(Debug, PartialEq, Clone)]
+pub enum CommandOutput<OrderID, AccountID> {
+    None,
+    Execution(Execution<OrderID>),
+    /// execution of every leg of `Command::BasketOrder`
+    Basket(Vec<Execution<OrderID>>),
+    /// orders cancelled by `Command::ExpireOrders`
+    Expired(Vec<OrderID>),
+    Settlement(Settlement<OrderID, AccountID>),
+}
@@ -0,0 +1,35 @@
+use rust_decimal::Decimal;
+
+/// Aggregated price levels of an outcome order book
+#[derive(Debug, PartialEq, Clone)]
+pub struct Depth {
+    /// bid levels from highest to lowest price
+    pub bids: Vec<PriceLevel>,
+    /// ask levels from lowest to highest price
+    pub asks: Vec<PriceLevel>,
+}
+
+/// Total quantity available at a price
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct PriceLevel {
+    pub price: Decimal,
+    pub quantity: Decimal,
+}
+
+/// Top of book of an outcome, both direct and implied by the other outcome order books
+#[derive(Debug, PartialEq, Clone)]
+pub struct Quote {
+    pub outcome: usize,
+    /// best bid resting in the outcome order book
+    pub bid: Option<PriceLevel>,
+    /// best ask resting in the outcome order book
+    pub ask: Option<PriceLevel>,
+    /// best bid implied by the asks of all other outcomes
+    pub implied_bid: Option<PriceLevel>,
+    /// best ask implied by the bids of all other outcomes
+    pub implied_ask: Option<PriceLevel>,
+    /// best of the direct and implied bids, with quantities combined at equal prices
+    pub best_bid: Option<PriceLevel>,
+    /// best of the direct and implied asks, with quantities combined at equal prices
+    pub best_ask: Option<PriceLevel>,
+}
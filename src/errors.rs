@@ -1,5 +1,298 @@
+#[derive(Debug, PartialEq, Clone)]
+pub enum New {
+    TooFewOutcomes,
+    NonPositiveContractPrice,
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum ProcessLimitOrder {
-    OrderValidationFailed
+    OrderValidationFailed,
+    DuplicateOrderId,
+    UnknownAccount,
+    InsufficientCollateral,
+    PostOnlyWouldMatch,
+    PriceOffTick,
+    QuantityOffLot,
+    QuantityBelowMinimum,
+    MarketResolved,
+    /// market is closed, or the order can not be queued while it is not open
+    MarketNotOpen,
+    ArithmeticOverflow,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum ProcessMarketOrder {
+    OrderValidationFailed,
+    DuplicateOrderId,
+    UnknownAccount,
+    InsufficientCollateral,
+    PriceOffTick,
+    QuantityOffLot,
+    QuantityBelowMinimum,
+    MarketResolved,
+    MarketNotOpen,
+    ArithmeticOverflow,
+}
+
+impl From<ProcessLimitOrder> for ProcessMarketOrder {
+    fn from(e: ProcessLimitOrder) -> Self {
+        match e {
+            ProcessLimitOrder::OrderValidationFailed => Self::OrderValidationFailed,
+            ProcessLimitOrder::DuplicateOrderId => Self::DuplicateOrderId,
+            ProcessLimitOrder::UnknownAccount => Self::UnknownAccount,
+            ProcessLimitOrder::InsufficientCollateral => Self::InsufficientCollateral,
+            // market orders are never post only
+            ProcessLimitOrder::PostOnlyWouldMatch => Self::OrderValidationFailed,
+            ProcessLimitOrder::PriceOffTick => Self::PriceOffTick,
+            ProcessLimitOrder::QuantityOffLot => Self::QuantityOffLot,
+            ProcessLimitOrder::QuantityBelowMinimum => Self::QuantityBelowMinimum,
+            ProcessLimitOrder::MarketResolved => Self::MarketResolved,
+            ProcessLimitOrder::MarketNotOpen => Self::MarketNotOpen,
+            ProcessLimitOrder::ArithmeticOverflow => Self::ArithmeticOverflow,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum ProcessBasketOrder {
+    OrderValidationFailed,
+    DuplicateOrderId,
+    UnknownAccount,
+    InsufficientCollateral,
+    PriceOffTick,
+    QuantityOffLot,
+    QuantityBelowMinimum,
+    MarketResolved,
+    MarketNotOpen,
+    ArithmeticOverflow,
+}
+
+impl From<ProcessMarketOrder> for ProcessBasketOrder {
+    fn from(e: ProcessMarketOrder) -> Self {
+        match e {
+            ProcessMarketOrder::OrderValidationFailed => Self::OrderValidationFailed,
+            ProcessMarketOrder::DuplicateOrderId => Self::DuplicateOrderId,
+            ProcessMarketOrder::UnknownAccount => Self::UnknownAccount,
+            ProcessMarketOrder::InsufficientCollateral => Self::InsufficientCollateral,
+            ProcessMarketOrder::PriceOffTick => Self::PriceOffTick,
+            ProcessMarketOrder::QuantityOffLot => Self::QuantityOffLot,
+            ProcessMarketOrder::QuantityBelowMinimum => Self::QuantityBelowMinimum,
+            ProcessMarketOrder::MarketResolved => Self::MarketResolved,
+            ProcessMarketOrder::MarketNotOpen => Self::MarketNotOpen,
+            ProcessMarketOrder::ArithmeticOverflow => Self::ArithmeticOverflow,
+        }
+    }
+}
+
+impl From<ProcessLimitOrder> for ProcessBasketOrder {
+    fn from(e: ProcessLimitOrder) -> Self {
+        ProcessMarketOrder::from(e).into()
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum SubmitStopOrder {
+    OrderValidationFailed,
+    DuplicateOrderId,
+    PriceOffTick,
+    QuantityOffLot,
+    QuantityBelowMinimum,
+    MarketResolved,
+    MarketNotOpen,
+}
+
+impl From<ProcessLimitOrder> for SubmitStopOrder {
+    fn from(e: ProcessLimitOrder) -> Self {
+        match e {
+            ProcessLimitOrder::DuplicateOrderId => Self::DuplicateOrderId,
+            ProcessLimitOrder::PriceOffTick => Self::PriceOffTick,
+            ProcessLimitOrder::QuantityOffLot => Self::QuantityOffLot,
+            ProcessLimitOrder::QuantityBelowMinimum => Self::QuantityBelowMinimum,
+            ProcessLimitOrder::MarketResolved => Self::MarketResolved,
+            ProcessLimitOrder::MarketNotOpen => Self::MarketNotOpen,
+            // accounts and collateral are only checked once the stop order triggers
+            _ => Self::OrderValidationFailed,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum CancelOrder {
+    UnknownOrder,
+    ArithmeticOverflow,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum AmendOrder {
+    UnknownOrder,
+    OrderValidationFailed,
+    InsufficientCollateral,
+    /// post only order would match at its new price
+    PostOnlyWouldMatch,
+    PriceOffTick,
+    QuantityOffLot,
+    QuantityBelowMinimum,
+    MarketResolved,
+    MarketNotOpen,
+    ArithmeticOverflow,
+}
+
+impl From<ProcessLimitOrder> for AmendOrder {
+    fn from(e: ProcessLimitOrder) -> Self {
+        match e {
+            ProcessLimitOrder::OrderValidationFailed => Self::OrderValidationFailed,
+            // the amended order already exists and belongs to a known account
+            ProcessLimitOrder::DuplicateOrderId | ProcessLimitOrder::UnknownAccount => {
+                Self::UnknownOrder
+            }
+            ProcessLimitOrder::InsufficientCollateral => Self::InsufficientCollateral,
+            ProcessLimitOrder::PostOnlyWouldMatch => Self::PostOnlyWouldMatch,
+            ProcessLimitOrder::PriceOffTick => Self::PriceOffTick,
+            ProcessLimitOrder::QuantityOffLot => Self::QuantityOffLot,
+            ProcessLimitOrder::QuantityBelowMinimum => Self::QuantityBelowMinimum,
+            ProcessLimitOrder::MarketResolved => Self::MarketResolved,
+            ProcessLimitOrder::MarketNotOpen => Self::MarketNotOpen,
+            ProcessLimitOrder::ArithmeticOverflow => Self::ArithmeticOverflow,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum ExpireOrders {
+    MarketResolved,
+    ArithmeticOverflow,
+}
+
+impl From<ExpireOrders> for ProcessLimitOrder {
+    fn from(e: ExpireOrders) -> Self {
+        match e {
+            ExpireOrders::MarketResolved => Self::MarketResolved,
+            ExpireOrders::ArithmeticOverflow => Self::ArithmeticOverflow,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Resolve {
+    MarketResolved,
+    InvalidOutcome,
+    InvalidPayouts,
+    ArithmeticOverflow,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Deposit {
+    NonPositiveAmount,
+    ArithmeticOverflow,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Withdraw {
+    NonPositiveAmount,
+    UnknownAccount,
+    InsufficientFunds,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum MintCompleteSet {
+    NonPositiveQuantity,
+    QuantityOffLot,
+    UnknownAccount,
+    InsufficientFunds,
+    MarketResolved,
+    ArithmeticOverflow,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum MergeCompleteSet {
+    NonPositiveQuantity,
+    QuantityOffLot,
+    UnknownAccount,
+    /// account does not hold quantity unreserved contracts of every outcome
+    InsufficientContracts,
+    MarketResolved,
+    ArithmeticOverflow,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum SetMarketState {
+    MarketResolved,
+    /// resolved states are only reached by resolving the market
+    InvalidState,
+    /// the market can not move from its current state to the new one
+    InvalidTransition,
 }
 
+#[derive(Debug, PartialEq, Clone)]
+pub enum HaltOutcome {
+    MarketResolved,
+    InvalidOutcome,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum SetTradingRules {
+    NonPositiveSize,
+    ContractPriceOffTick,
+    /// a resting order is not on the new price or quantity grid
+    RestingOrderOffGrid,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum SetFeeSchedule {
+    /// tiers are not in strictly increasing min_volume
+    UnsortedTiers,
+    /// flat maker fees are charged on every match of a resting order,
+    /// so collateral can not cover them
+    FlatMakerFee,
+    /// an account can not cover the maker fees of its resting orders
+    InsufficientCollateral,
+    ArithmeticOverflow,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Apply {
+    /// `Command::New` can only start a command log
+    UnexpectedNew,
+    Deposit(Deposit),
+    Withdraw(Withdraw),
+    MintCompleteSet(MintCompleteSet),
+    MergeCompleteSet(MergeCompleteSet),
+    ProcessLimitOrder(ProcessLimitOrder),
+    ProcessMarketOrder(ProcessMarketOrder),
+    ProcessBasketOrder(ProcessBasketOrder),
+    CancelOrder(CancelOrder),
+    AmendOrder(AmendOrder),
+    SubmitStopOrder(SubmitStopOrder),
+    ExpireOrders(ExpireOrders),
+    Resolve(Resolve),
+    SetTradingRules(SetTradingRules),
+    SetFeeSchedule(SetFeeSchedule),
+    SetMarketState(SetMarketState),
+    HaltOutcome(HaltOutcome),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Replay {
+    /// command log does not start with `Command::New`
+    MissingNew,
+    New(New),
+    /// command at index of the command log failed
+    Apply {
+        index: usize,
+        error: Apply,
+    },
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Restore {
+    TooFewOutcomes,
+    NonPositiveContractPrice,
+    /// resting orders are duplicated, of unknown outcomes or cross each other,
+    /// or positions, stop, queued or account orders are of unknown outcomes
+    InvalidOrder,
+    /// accounts do not hold one position per outcome, or orders are placed on
+    /// behalf of unknown accounts
+    InvalidAccount,
+    /// last trade prices or halted outcomes do not have one entry per outcome
+    OutcomesMismatch,
+}
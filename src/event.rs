@@ -0,0 +1,123 @@
+use std::sync::mpsc::Sender;
+
+use rust_decimal::Decimal;
+use rust_ob::Side;
+
+use crate::{execution::Fill, settlement::Resolution, state::MarketState};
+
+/// State change of a `PieOrderBook`
+#[derive(Debug, Clone)]
+pub enum Event<OrderID, AccountID = ()> {
+    /// order passed validation and is about to be matched.
+    /// price is `None` for market orders without a worst price.
+    OrderAccepted {
+        order: OrderID,
+        outcome: usize,
+        side: Side,
+        price: Option<Decimal>,
+        quantity: Decimal,
+    },
+    /// unfilled quantity of an order was added to its outcome order book
+    OrderRested {
+        order: OrderID,
+        outcome: usize,
+        side: Side,
+        price: Decimal,
+        quantity: Decimal,
+    },
+    Fill(Fill<OrderID>),
+    /// resting or queued order was removed with quantity left
+    OrderCancelled {
+        order: OrderID,
+        outcome: usize,
+        side: Side,
+        price: Decimal,
+        quantity: Decimal,
+    },
+    /// total resting quantity at a price level changed. A quantity of zero
+    /// means the level was removed.
+    BookLevelChanged {
+        outcome: usize,
+        side: Side,
+        price: Decimal,
+        quantity: Decimal,
+    },
+    /// price or quantity of a resting order was changed
+    OrderAmended {
+        order: OrderID,
+        outcome: usize,
+        side: Side,
+        price: Decimal,
+        quantity: Decimal,
+    },
+    /// displayed quantity of an iceberg order was filled and replenished from its
+    /// hidden rest, behind all orders at its price. Emitted while matching,
+    /// before the fills of the execution that filled it.
+    OrderReplenished {
+        order: OrderID,
+        outcome: usize,
+        side: Side,
+        price: Decimal,
+        quantity: Decimal,
+    },
+    /// limit order was validated and queued until its outcome can be traded
+    OrderQueued {
+        order: OrderID,
+        outcome: usize,
+        side: Side,
+        price: Decimal,
+        quantity: Decimal,
+    },
+    /// queued order was rejected once it was processed, e.g. for lack of collateral
+    QueuedOrderRejected {
+        order: OrderID,
+    },
+    MarketStateChanged(MarketState),
+    OutcomeHalted {
+        outcome: usize,
+    },
+    OutcomeResumed {
+        outcome: usize,
+    },
+    /// stop order was triggered and is submitted next
+    StopTriggered {
+        order: OrderID,
+    },
+    /// triggered stop order was rejected, e.g. for lack of collateral
+    StopRejected {
+        order: OrderID,
+    },
+    /// stop order was cancelled, expired or dropped at resolution before it triggered
+    StopCancelled {
+        order: OrderID,
+    },
+    MarketResolved(Resolution),
+    Deposited {
+        account: AccountID,
+        amount: Decimal,
+    },
+    Withdrawn {
+        account: AccountID,
+        amount: Decimal,
+    },
+    CompleteSetsMinted {
+        account: AccountID,
+        quantity: Decimal,
+    },
+    CompleteSetsMerged {
+        account: AccountID,
+        quantity: Decimal,
+    },
+}
+
+/// Receives the events of a `PieOrderBook` in the order they happen
+pub trait EventSink<OrderID, AccountID = ()> {
+    fn emit(&mut self, event: Event<OrderID, AccountID>);
+}
+
+/// Forwards events to a channel. Events are dropped once the receiver is gone.
+impl<OrderID, AccountID> EventSink<OrderID, AccountID> for Sender<Event<OrderID, AccountID>> {
+    fn emit(&mut self, event: Event<OrderID, AccountID>) {
+        let _ = self.send(event);
+    }
+}
@@ -0,0 +1,45 @@
+use rust_decimal::Decimal;
+use rust_ob::OrderMatch;
+
+use crate::fee::FeeCharge;
+
+/// Result of processing an order
+#[derive(Debug, PartialEq, Clone)]
+pub struct Execution<OrderID> {
+    /// matches aggregated per order. Costs do not include fees.
+    pub matches: Vec<OrderMatch<OrderID>>,
+    /// every individual fill, in the order they happened
+    pub fills: Vec<Fill<OrderID>>,
+    /// quantity of the order that was neither filled nor removed by self trade
+    /// prevention. For good till cancel limit orders this quantity rests in the
+    /// order book, unless the order itself was cancelled.
+    pub unfilled: Decimal,
+    /// orders cancelled by self trade prevention, including the incoming order
+    pub cancelled: Vec<OrderID>,
+    /// fees charged for the matches, empty without fee schedule
+    pub fees: Vec<FeeCharge<OrderID>>,
+}
+
+/// A resting (maker) order being matched by an incoming (taker) order
+#[derive(Debug, PartialEq, Clone)]
+pub struct Fill<OrderID> {
+    pub maker: OrderID,
+    pub taker: OrderID,
+    /// outcome of the maker order
+    pub outcome: usize,
+    /// price of the maker order
+    pub price: Decimal,
+    pub quantity: Decimal,
+    pub match_type: MatchType,
+}
+
+/// How a fill came about
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum MatchType {
+    /// maker and taker are on opposite sides of the same outcome order book
+    Direct,
+    /// taker buy and maker buys of all other outcomes form a complete set
+    Mint,
+    /// taker sell and maker sells of all other outcomes dissolve a complete set
+    Merge,
+}
@@ -0,0 +1,112 @@
+use rust_decimal::Decimal;
+
+/// Fee charged per order match
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FeeRate {
+    /// basis points of the absolute match cost
+    Bps(Decimal),
+    /// fixed amount per match
+    Flat(Decimal),
+    /// amount per matched contract
+    PerContract(Decimal),
+}
+
+impl FeeRate {
+    /// Returns the fee of a match of quantity contracts for cost
+    pub fn fee(&self, quantity: Decimal, cost: Decimal) -> Option<Decimal> {
+        match *self {
+            FeeRate::Bps(bps) => cost
+                .abs()
+                .checked_mul(bps)
+                .and_then(|fee| fee.checked_div(Decimal::from(10_000))),
+            FeeRate::Flat(amount) => Some(amount),
+            FeeRate::PerContract(amount) => quantity.checked_mul(amount),
+        }
+    }
+}
+
+/// Fee rates of accounts that traded at least min_volume contracts
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FeeTier {
+    pub min_volume: Decimal,
+    pub maker: FeeRate,
+    pub taker: FeeRate,
+}
+
+/// Maker and taker fees of a `PieOrderBook`. Negative fees are rebates.
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FeeSchedule {
+    pub maker: FeeRate,
+    pub taker: FeeRate,
+    /// tiers in increasing min_volume. The highest tier an account reached with its
+    /// volume before a match replaces the maker and taker rates.
+    pub tiers: Vec<FeeTier>,
+}
+
+impl FeeSchedule {
+    /// Returns the rate for a match providing liquidity by an account with volume
+    pub fn rate(&self, liquidity: Liquidity, volume: Decimal) -> FeeRate {
+        let (maker, taker) = self
+            .tiers
+            .iter()
+            .rev()
+            .find(|tier| tier.min_volume <= volume)
+            .map_or((self.maker, self.taker), |tier| (tier.maker, tier.taker));
+
+        match liquidity {
+            Liquidity::Maker => maker,
+            Liquidity::Taker => taker,
+        }
+    }
+
+    /// Returns the highest fee a match of quantity contracts for cost can be charged
+    /// by an account with volume, at its current tier or any tier it can still reach.
+    /// Rebates count as no fee.
+    pub(crate) fn max_fee(
+        &self,
+        liquidity: Liquidity,
+        volume: Decimal,
+        quantity: Decimal,
+        cost: Decimal,
+    ) -> Option<Decimal> {
+        let higher_tiers = self
+            .tiers
+            .iter()
+            .filter(|tier| tier.min_volume > volume)
+            .map(|tier| match liquidity {
+                Liquidity::Maker => tier.maker,
+                Liquidity::Taker => tier.taker,
+            });
+
+        std::iter::once(self.rate(liquidity, volume))
+            .chain(higher_tiers)
+            .try_fold(Decimal::ZERO, |max, rate| {
+                Some(max.max(rate.fee(quantity, cost)?))
+            })
+    }
+
+    /// whether a positive flat maker fee is charged at any tier
+    pub(crate) fn has_flat_maker_fee(&self) -> bool {
+        std::iter::once(self.maker)
+            .chain(self.tiers.iter().map(|tier| tier.maker))
+            .any(|rate| matches!(rate, FeeRate::Flat(amount) if amount > Decimal::ZERO))
+    }
+}
+
+/// Whether an order added or removed liquidity
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Liquidity {
+    Maker,
+    Taker,
+}
+
+/// Fee charged to an order for its match in an execution
+#[derive(Debug, PartialEq, Clone)]
+pub struct FeeCharge<OrderID> {
+    pub order: OrderID,
+    pub liquidity: Liquidity,
+    pub amount: Decimal,
+}
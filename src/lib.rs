@@ -1,5 +1,34 @@
+mod account;
+mod clock;
+mod command;
+mod depth;
 pub mod errors;
+mod event;
+mod execution;
+mod fee;
+mod order;
 mod pieorderbook;
+mod probability;
+mod rules;
+mod settlement;
+mod snapshot;
+mod state;
 
+pub use account::Account;
+pub use clock::{Clock, ManualClock, SystemClock};
+pub use command::{Command, CommandOutput};
+pub use depth::{Depth, PriceLevel, Quote};
+pub use event::{Event, EventSink};
+pub use execution::{Execution, Fill, MatchType};
+pub use fee::{FeeCharge, FeeRate, FeeSchedule, FeeTier, Liquidity};
+pub use order::{
+    Order, OrderOptions, Position, PostOnly, SelfTradePrevention, StopOrder, StopTrigger,
+    TimeInForce,
+};
 pub use pieorderbook::PieOrderBook;
+pub use probability::{OutcomeProbability, Probabilities};
+pub use rules::TradingRules;
 pub use rust_ob::{OrderMatch, Side};
+pub use settlement::{AccountPayout, Payout, Resolution, Settlement};
+pub use snapshot::Snapshot;
+pub use state::MarketState;
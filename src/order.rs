@@ -0,0 +1,149 @@
+use rust_decimal::Decimal;
+use rust_ob::Side;
+
+/// Resting order inside `PieOrderBook`
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Order {
+    pub outcome: usize,
+    #[cfg_attr(feature = "serde", serde(with = "crate::snapshot::SideDef"))]
+    pub side: Side,
+    pub price: Decimal,
+    /// remaining quantity displayed in the order book
+    pub quantity: Decimal,
+    /// remaining quantity of iceberg orders hidden from the order book
+    pub hidden: Decimal,
+    /// quantity iceberg orders display at once
+    pub peak: Option<Decimal>,
+    /// time priority, lower sequences were added to the order book earlier
+    pub(crate) sequence: u64,
+    /// time the order expires at, for good till time orders
+    pub expires_at: Option<u64>,
+    /// post only mode, applied again when the order is amended
+    pub post_only: Option<PostOnly>,
+}
+
+impl Order {
+    /// Returns the displayed and hidden remaining quantity
+    pub fn remaining_quantity(&self) -> Decimal {
+        self.quantity + self.hidden
+    }
+}
+
+/// Contracts acquired by an order over its lifetime
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Position {
+    pub outcome: usize,
+    /// contracts held, negative if the order sold more than it bought
+    pub quantity: Decimal,
+    /// total cost of the acquired contracts, negative if the order received funds
+    pub cost: Decimal,
+}
+
+/// How long a limit order stays active
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TimeInForce {
+    /// remaining quantity rests in the order book until matched or cancelled
+    #[default]
+    GoodTillCancel,
+    /// like `GoodTillCancel`, but expires at the given time in milliseconds
+    /// since the unix epoch, see `PieOrderBook::expire_orders`
+    GoodTillTime(u64),
+    /// remaining quantity is dropped after matching
+    ImmediateOrCancel,
+    /// order is only processed if its whole quantity can be matched immediately
+    FillOrKill,
+}
+
+impl TimeInForce {
+    /// time a good till time order expires at
+    pub(crate) fn expires_at(self) -> Option<u64> {
+        match self {
+            TimeInForce::GoodTillTime(expires_at) => Some(expires_at),
+            _ => None,
+        }
+    }
+}
+
+/// What to do with a post only limit order that would match on arrival
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PostOnly {
+    Reject,
+    /// move the price one tick away from the best opposite price.
+    /// Orders can only be repriced while `TradingRules::tick_size` is set.
+    Reprice,
+}
+
+/// What to do when an order would match a resting order of the same account
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SelfTradePrevention {
+    /// cancel the remaining quantity of the incoming order
+    CancelNewest,
+    /// cancel the resting order and continue matching
+    CancelOldest,
+    /// cancel the resting order and the remaining quantity of the incoming order
+    CancelBoth,
+    /// reduce both orders by their overlapping quantity without trading
+    Decrement,
+}
+
+/// Optional parameters of an order
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OrderOptions<AccountID> {
+    /// account the order is placed on behalf of
+    pub account: Option<AccountID>,
+    /// ignored by market orders
+    pub time_in_force: TimeInForce,
+    /// order may only add liquidity, ignored by market orders
+    pub post_only: Option<PostOnly>,
+    /// prevents matching resting orders of the same account, including through
+    /// the other outcome order books. Ignored for orders without account.
+    pub self_trade_prevention: Option<SelfTradePrevention>,
+    /// makes a limit order an iceberg order that displays at most this quantity
+    /// in the order book. The hidden rest replenishes the displayed quantity
+    /// each time it is filled, behind all orders at its price.
+    pub display_quantity: Option<Decimal>,
+}
+
+impl<AccountID> Default for OrderOptions<AccountID> {
+    fn default() -> Self {
+        OrderOptions {
+            account: None,
+            time_in_force: TimeInForce::default(),
+            post_only: None,
+            self_trade_prevention: None,
+            display_quantity: None,
+        }
+    }
+}
+
+/// Price a stop order is triggered by
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StopTrigger {
+    /// last price the outcome traded at, directly or through the other outcome order books
+    LastTrade,
+    /// effective best ask for buy stops and effective best bid for sell stops
+    Quote,
+}
+
+/// Order held off-book until the price of its outcome reaches trigger_price.
+/// Buy stops trigger at or above trigger_price, sell stops at or below it.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StopOrder<AccountID> {
+    pub outcome: usize,
+    #[cfg_attr(feature = "serde", serde(with = "crate::snapshot::SideDef"))]
+    pub side: Side,
+    pub quantity: Decimal,
+    pub trigger: StopTrigger,
+    pub trigger_price: Decimal,
+    /// limit price of the triggered order, a market order is submitted if `None`
+    pub limit_price: Option<Decimal>,
+    pub options: OrderOptions<AccountID>,
+}
@@ -3,15 +3,109 @@ use std::{collections::HashMap, fmt::Display, hash::Hash};
 use rust_decimal::Decimal;
 use rust_ob::{OrderBook, OrderMatch, Side};
 
-use crate::errors;
+use crate::{
+    account::Account,
+    clock::SystemClock,
+    command::Command,
+    errors,
+    event::Event,
+    execution::{Execution, Fill, MatchType},
+    fee::FeeSchedule,
+    order::{Order, OrderOptions, Position, PostOnly, TimeInForce},
+    rules::TradingRules,
+    settlement::{AccountPayout, Payout, Resolution, Settlement},
+    state::MarketState,
+};
+
+mod amend;
+mod basket;
+mod commands;
+mod events;
+mod expiry;
+mod fees;
+mod iceberg;
+mod ledger;
+mod levels;
+mod rules;
+mod self_trade;
+mod snapshot;
+mod state;
+mod stops;
+
+use events::BoxedEventSink;
+use expiry::BoxedClock;
+pub(crate) use ledger::AccountOrder;
+use levels::PriceLevels;
+use self_trade::SelfTradeAction;
+use state::Admission;
+pub(crate) use state::QueuedOrder;
+use stops::StopBook;
+
+/// whether two orders are on the same side of a book, `Side` can not be compared
+fn same_side(a: Side, b: Side) -> bool {
+    matches!((a, b), (Side::Buy, Side::Buy) | (Side::Sell, Side::Sell))
+}
+
+/// order books an incoming order is matched against
+#[derive(Debug, Clone, Copy)]
+enum MatchSource {
+    Own,
+    Others,
+}
 
 #[derive(Debug)]
-pub struct PieOrderBook<OrderID>
+pub struct PieOrderBook<OrderID, AccountID = ()>
 where
     OrderID: Copy + PartialEq + Eq + Hash,
+    AccountID: Copy + PartialEq + Eq + Hash,
 {
     contract_price: Decimal,
     order_books: Vec<OrderBook<OrderID>>,
+
+    // every resting order is in: order_index AND order_books[order.outcome]
+    order_index: HashMap<OrderID, Order>,
+    // displayed quantity of every price level of order_books
+    levels: PriceLevels,
+    // sequence of the next order added to an order book
+    next_sequence: u64,
+    // every order that has been matched at least once
+    positions: HashMap<OrderID, Position>,
+    // keys of positions in the order they were first matched
+    position_ids: Vec<OrderID>,
+
+    accounts: HashMap<AccountID, Account>,
+    // keys of accounts in the order they were created
+    account_ids: Vec<AccountID>,
+    // every order that was placed on behalf of an account
+    order_accounts: HashMap<OrderID, AccountOrder<AccountID>>,
+    // keys of order_accounts in the order they were placed
+    order_account_ids: Vec<OrderID>,
+
+    // stop orders held off-book, in the order they were submitted
+    stop_orders: StopBook<OrderID, AccountID>,
+    // last price every outcome traded at
+    last_trade_prices: Vec<Option<Decimal>>,
+    // latest time orders were expired at
+    expired_until: Option<u64>,
+    // no resting, stop or queued order expires before it
+    earliest_expiry: Option<u64>,
+
+    market_state: MarketState,
+    // whether trading of every outcome is halted
+    halted: Vec<bool>,
+    // limit orders waiting for their outcome to be tradable, in the order they were queued
+    queued_orders: Vec<(OrderID, QueuedOrder<AccountID>)>,
+
+    resolution: Option<Resolution>,
+    trading_rules: TradingRules,
+    fee_schedule: Option<FeeSchedule>,
+    // sum of all fees charged
+    collected_fees: Decimal,
+
+    event_sink: Option<BoxedEventSink<OrderID, AccountID>>,
+    clock: BoxedClock,
+    // every successful state changing operation, starting with New or the restored snapshot
+    command_log: Vec<Command<OrderID, AccountID>>,
 }
 
 impl<OrderID> PieOrderBook<OrderID>
@@ -20,10 +114,26 @@ where
 {
     /// Create new `PieOrderBook`
     ///
-    /// IMPORTANT: This function panics if outcomes is less than 2
-    pub fn new(contract_price: Decimal, outcomes: usize) -> Self {
+    /// outcomes must always be 2 or greater and contract_price must be positive
+    pub fn new(contract_price: Decimal, outcomes: usize) -> Result<Self, errors::New> {
+        Self::with_accounts(contract_price, outcomes)
+    }
+}
+
+impl<OrderID, AccountID> PieOrderBook<OrderID, AccountID>
+where
+    OrderID: Copy + PartialEq + Eq + Hash,
+    AccountID: Copy + PartialEq + Eq + Hash,
+{
+    /// Create new `PieOrderBook` that accepts orders on behalf of accounts
+    ///
+    /// outcomes must always be 2 or greater and contract_price must be positive
+    pub fn with_accounts(contract_price: Decimal, outcomes: usize) -> Result<Self, errors::New> {
         if outcomes < 2 {
-            panic!("PieOrderBook: new: outcomes must always be 2 or greater")
+            return Err(errors::New::TooFewOutcomes);
+        }
+        if contract_price <= Decimal::ZERO {
+            return Err(errors::New::NonPositiveContractPrice);
         }
 
         let mut order_books = Vec::new();
@@ -31,150 +141,642 @@ where
             order_books.push(OrderBook::new())
         }
 
-        PieOrderBook {
+        Ok(PieOrderBook {
             contract_price,
             order_books,
-        }
+            order_index: HashMap::new(),
+            levels: PriceLevels::new(outcomes),
+            next_sequence: 0,
+            positions: HashMap::new(),
+            position_ids: Vec::new(),
+            accounts: HashMap::new(),
+            account_ids: Vec::new(),
+            order_accounts: HashMap::new(),
+            order_account_ids: Vec::new(),
+            stop_orders: StopBook::new(outcomes),
+            last_trade_prices: vec![None; outcomes],
+            expired_until: None,
+            earliest_expiry: None,
+            market_state: MarketState::default(),
+            halted: vec![false; outcomes],
+            queued_orders: Vec::new(),
+            resolution: None,
+            trading_rules: TradingRules::default(),
+            fee_schedule: None,
+            collected_fees: Decimal::ZERO,
+            event_sink: None,
+            clock: BoxedClock(Box::new(SystemClock)),
+            command_log: vec![Command::New {
+                contract_price,
+                outcomes,
+            }],
+        })
     }
 
     /// Process a new limit order
-    ///
-    /// IMPORTANT: PieOrderBook will possibly panic if another order
-    /// with the same id exists already inside PieOrderBook
     pub fn process_limit_order(
         &mut self,
         id: OrderID,
         outcome: usize,
         side: Side,
         price: Decimal,
-        mut quantity: Decimal,
+        quantity: Decimal,
     ) -> Result<Vec<OrderMatch<OrderID>>, errors::ProcessLimitOrder> {
-        // order parameter validation
-        let failed_validation = outcome >= self.order_books.len()
-            || price <= Decimal::ZERO
-            || price >= self.contract_price
-            || quantity <= Decimal::ZERO;
+        self.process_limit_order_with_options(
+            id,
+            outcome,
+            side,
+            price,
+            quantity,
+            OrderOptions::default(),
+        )
+        .map(|execution| execution.matches)
+    }
 
-        if failed_validation {
+    /// Process a new limit order with options
+    ///
+    /// Fills of orders placed on behalf of an account update the cash
+    /// and positions of that account. The account must have been created
+    /// with a deposit beforehand and must have enough available collateral
+    /// for the worst case loss of the order.
+    ///
+    /// A `FillOrKill` order that can not be filled completely is dropped
+    /// without any matches.
+    ///
+    /// A post only order that would match in its own outcome order book or
+    /// through the other outcome order books is rejected or repriced.
+    ///
+    /// Before the market opens, while it is halted or while the outcome is halted,
+    /// the order is validated and queued without any matches, locking its collateral
+    /// like a resting order. See `set_market_state`.
+    ///
+    /// Good till time orders must expire after the current time of the order book clock.
+    /// Orders that expired by then are expired before the order is matched.
+    pub fn process_limit_order_with_options(
+        &mut self,
+        id: OrderID,
+        outcome: usize,
+        side: Side,
+        price: Decimal,
+        quantity: Decimal,
+        options: OrderOptions<AccountID>,
+    ) -> Result<Execution<OrderID>, errors::ProcessLimitOrder> {
+        self.process_limit_order_at(self.now(), id, outcome, side, price, quantity, options)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn process_limit_order_at(
+        &mut self,
+        now: u64,
+        id: OrderID,
+        outcome: usize,
+        side: Side,
+        price: Decimal,
+        quantity: Decimal,
+        options: OrderOptions<AccountID>,
+    ) -> Result<Execution<OrderID>, errors::ProcessLimitOrder> {
+        self.expire_stale_orders(now)?;
+        if Self::expires_by(options.time_in_force, now) {
             return Err(errors::ProcessLimitOrder::OrderValidationFailed);
         }
 
-        // process order
-        let mut order_match_map: HashMap<OrderID, OrderMatch<OrderID>> = HashMap::new();
-
-        while quantity > Decimal::ZERO {
-            let (own_price, own_quantity) =
-                self.get_order_book_best_price_quantity(outcome, side.opposite());
-            let (others_price, others_quantity) =
-                self.get_other_order_books_best_price_quantity(outcome, side.opposite());
+        let command = Command::LimitOrder {
+            id,
+            outcome,
+            side,
+            price,
+            quantity,
+            options: options.clone(),
+            now,
+        };
 
-            if match side {
-                // buys match to own order book at same price
-                Side::Buy => {
-                    price >= own_price && !(own_price > others_price && !others_quantity.is_zero())
+        let execution = match self.admission(outcome)? {
+            Admission::Match => {
+                self.execute_limit_order(id, outcome, side, price, quantity, options)?
+            }
+            Admission::Queue => {
+                self.validate_limit_order(id, outcome, price, quantity, &options)?;
+                let reserved = match options.account {
+                    Some(account) => Some((
+                        account,
+                        self.check_collateral(account, outcome, side, price, quantity)?,
+                    )),
+                    None => None,
+                };
+                let self_trade_prevention = options.self_trade_prevention;
+                let order = QueuedOrder {
+                    outcome,
+                    side,
+                    price,
+                    quantity,
+                    options,
+                };
+                self.queue_order(id, order);
+                // queued orders lock collateral like resting orders
+                if let Some((account, reserved)) = reserved {
+                    self.add_account_order(id, account, outcome, reserved, self_trade_prevention)?;
+                    self.update_order_collateral(id)?;
                 }
-                // sells match to other order books at same price
-                Side::Sell => {
-                    price <= own_price && !(own_price <= others_price && !others_quantity.is_zero())
+
+                Execution {
+                    matches: Vec::new(),
+                    fills: Vec::new(),
+                    unfilled: quantity,
+                    cancelled: Vec::new(),
+                    fees: Vec::new(),
                 }
-            } {
-                // match in own outcome order book
-                let satisfied_quantity = own_quantity.min(quantity);
+            }
+        };
+        self.command_log.push(command);
+        self.trigger_stop_orders();
 
-                let order_match_vec = self.order_books[outcome]
-                            .process_market_order(id, side, satisfied_quantity)
-                            .expect("PieOrderBook::process_limit_order: order with id already exists in own outcome OrderBook");
+        Ok(execution)
+    }
 
-                for order_match in order_match_vec {
-                    Self::add_order_match_to_map(&mut order_match_map, &order_match)
-                }
+    fn execute_limit_order(
+        &mut self,
+        id: OrderID,
+        outcome: usize,
+        side: Side,
+        mut price: Decimal,
+        quantity: Decimal,
+        options: OrderOptions<AccountID>,
+    ) -> Result<Execution<OrderID>, errors::ProcessLimitOrder> {
+        // order parameter validation
+        self.validate_limit_order(id, outcome, price, quantity, &options)?;
 
-                quantity = quantity
-                    .checked_sub(satisfied_quantity)
-                    .expect("PieOrderBook: subtraction overflow");
-            } else if match side {
-                Side::Buy => price >= others_price && !others_quantity.is_zero(),
-                Side::Sell => price <= others_price && !others_quantity.is_zero(),
-            } {
-                // match in other outcome order books
-                let satisfied_quantity = others_quantity.min(quantity);
-
-                for i in 0..self.order_books.len() {
-                    if i == outcome {
-                        continue;
-                    }
+        if let Some(post_only) = options.post_only {
+            price = self.post_only_price(outcome, side, price, post_only)?;
+        }
+        let reserved = match options.account {
+            Some(account) => self.check_collateral(account, outcome, side, price, quantity)?,
+            None => Decimal::ZERO,
+        };
+        if let TimeInForce::FillOrKill = options.time_in_force {
+            if self.fillable_quantity(outcome, side, price, quantity, &options)? < quantity {
+                return Ok(Execution {
+                    matches: Vec::new(),
+                    fills: Vec::new(),
+                    unfilled: quantity,
+                    cancelled: Vec::new(),
+                    fees: Vec::new(),
+                });
+            }
+        }
+        if let Some(account) = options.account {
+            self.add_account_order(
+                id,
+                account,
+                outcome,
+                reserved,
+                options.self_trade_prevention,
+            )?;
+        }
+        self.emit(Event::OrderAccepted {
+            order: id,
+            outcome,
+            side,
+            price: Some(price),
+            quantity,
+        });
 
-                    let order_match_vec = self.order_books[i]
-                                .process_market_order(id, side.opposite(), satisfied_quantity)
-                                .expect("PieOrderBook::process_limit_order: order with id already exists in other outcome OrderBook");
+        // process order
+        let execution = self.match_order(id, outcome, side, price, quantity)?;
+        self.emit_execution(side, &execution);
+        let quantity = execution.unfilled;
 
-                    assert_ne!(order_match_vec.len(), 0);
+        // add remaining to outcome orderbook if not empty
+        let expires_at = options.time_in_force.expires_at();
+        let rest = matches!(
+            options.time_in_force,
+            TimeInForce::GoodTillCancel | TimeInForce::GoodTillTime(_)
+        ) && !execution.cancelled.contains(&id);
+        if rest && !quantity.is_zero() {
+            let order = Order {
+                outcome,
+                side,
+                price,
+                quantity,
+                hidden: Decimal::ZERO,
+                peak: options.display_quantity,
+                sequence: self.next_sequence,
+                expires_at,
+                post_only: options.post_only,
+            };
+            self.rest_order(id, order)?;
+        }
+        self.update_order_collateral(id)?;
 
-                    for order_match in order_match_vec.iter().rev().skip(1) {
-                        Self::add_order_match_to_map(&mut order_match_map, order_match)
-                    }
-                }
+        Ok(execution)
+    }
 
-                let mut cost = others_price
-                    .checked_mul(satisfied_quantity)
-                    .expect("PieOrderBook: multiplication overflow");
-                if let Side::Sell = side {
-                    cost.set_sign_negative(true);
-                }
+    /// Process a new market order. The order is matched against the own
+    /// outcome order book and the other outcome order books until quantity
+    /// is filled or no more orders are available.
+    ///
+    /// If worst_price is given, the order is not matched at prices worse than it.
+    /// Unfilled quantity is never added to the order book.
+    pub fn process_market_order(
+        &mut self,
+        id: OrderID,
+        outcome: usize,
+        side: Side,
+        quantity: Decimal,
+        worst_price: Option<Decimal>,
+    ) -> Result<Execution<OrderID>, errors::ProcessMarketOrder> {
+        self.process_market_order_with_options(
+            id,
+            outcome,
+            side,
+            quantity,
+            worst_price,
+            OrderOptions::default(),
+        )
+    }
 
-                Self::add_order_match_to_map(
-                    &mut order_match_map,
-                    &OrderMatch {
-                        order: id,
-                        quantity: satisfied_quantity,
-                        cost,
-                    },
-                );
-
-                quantity = quantity
-                    .checked_sub(satisfied_quantity)
-                    .expect("PieOrderBook: subtraction overflow");
-            } else {
-                // nothing satisfies
-                break;
-            }
+    /// Process a new market order with options
+    ///
+    /// Orders placed on behalf of an account without worst_price must have
+    /// collateral for the order filling at the worst possible price.
+    pub fn process_market_order_with_options(
+        &mut self,
+        id: OrderID,
+        outcome: usize,
+        side: Side,
+        quantity: Decimal,
+        worst_price: Option<Decimal>,
+        options: OrderOptions<AccountID>,
+    ) -> Result<Execution<OrderID>, errors::ProcessMarketOrder> {
+        self.process_market_order_at(
+            self.now(),
+            id,
+            outcome,
+            side,
+            quantity,
+            worst_price,
+            options,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn process_market_order_at(
+        &mut self,
+        now: u64,
+        id: OrderID,
+        outcome: usize,
+        side: Side,
+        quantity: Decimal,
+        worst_price: Option<Decimal>,
+        options: OrderOptions<AccountID>,
+    ) -> Result<Execution<OrderID>, errors::ProcessMarketOrder> {
+        self.expire_stale_orders(now)?;
+
+        let command = Command::MarketOrder {
+            id,
+            outcome,
+            side,
+            quantity,
+            worst_price,
+            options: options.clone(),
+            now,
+        };
+
+        if let Admission::Queue = self.admission(outcome)? {
+            return Err(errors::ProcessMarketOrder::MarketNotOpen);
         }
+        let execution =
+            self.execute_market_order(id, outcome, side, quantity, worst_price, options)?;
+        self.command_log.push(command);
+        self.trigger_stop_orders();
 
-        // add remaining to outcome orderbook if not empty
-        if !quantity.is_zero() {
-            assert_eq!(
-                self.order_books[outcome]
-                    .process_limit_order(id, side, price, quantity)
-                    .expect("PieOrderBook::process_limit_order: should never panic")
-                    .len(),
-                0
-            );
+        Ok(execution)
+    }
+
+    fn execute_market_order(
+        &mut self,
+        id: OrderID,
+        outcome: usize,
+        side: Side,
+        quantity: Decimal,
+        worst_price: Option<Decimal>,
+        options: OrderOptions<AccountID>,
+    ) -> Result<Execution<OrderID>, errors::ProcessMarketOrder> {
+        let price = self.accept_market_order(id, outcome, side, quantity, worst_price, &options)?;
+
+        // process order
+        let execution = self.match_order(id, outcome, side, price, quantity)?;
+        self.emit_execution(side, &execution);
+        self.update_order_collateral(id)?;
+
+        Ok(execution)
+    }
+
+    /// Validates a market order and records the account it is placed on behalf of.
+    /// Returns the worst price it matches at.
+    fn accept_market_order(
+        &mut self,
+        id: OrderID,
+        outcome: usize,
+        side: Side,
+        quantity: Decimal,
+        worst_price: Option<Decimal>,
+        options: &OrderOptions<AccountID>,
+    ) -> Result<Decimal, errors::ProcessMarketOrder> {
+        // order parameter validation
+        self.validate_order(id, outcome, quantity)?;
+
+        let failed_validation =
+            worst_price.is_some_and(|price| price <= Decimal::ZERO || price >= self.contract_price);
+        if failed_validation {
+            return Err(errors::ProcessMarketOrder::OrderValidationFailed);
         }
+        self.check_trading_rules(worst_price, quantity)?;
 
-        Ok(order_match_map.into_values().collect())
+        let price = worst_price.unwrap_or(match side {
+            Side::Buy => self.contract_price,
+            Side::Sell => Decimal::ZERO,
+        });
+        if let Some(account) = options.account {
+            let reserved = self.check_collateral(account, outcome, side, price, quantity)?;
+            self.add_account_order(
+                id,
+                account,
+                outcome,
+                reserved,
+                options.self_trade_prevention,
+            )?;
+        }
+        self.emit(Event::OrderAccepted {
+            order: id,
+            outcome,
+            side,
+            price: worst_price,
+            quantity,
+        });
+
+        Ok(price)
     }
 
-    /// Cancel an order
+    /// Cancel a resting or queued order
+    pub fn cancel_order(&mut self, id: OrderID) -> Result<(), errors::CancelOrder> {
+        let queued = self
+            .queued_orders
+            .iter()
+            .position(|(queued_id, _)| *queued_id == id);
+        if let Some(index) = queued {
+            let (_, order) = self.queued_orders.remove(index);
+            self.update_order_collateral(id)
+                .map_err(|_| errors::CancelOrder::ArithmeticOverflow)?;
+            self.emit_queued_order_cancelled(id, &order);
+        } else {
+            self.remove_resting_order(id)
+                .map_err(|_| errors::CancelOrder::ArithmeticOverflow)?
+                .ok_or(errors::CancelOrder::UnknownOrder)?;
+        }
+
+        self.command_log.push(Command::CancelOrder { id });
+        self.trigger_stop_orders();
+
+        Ok(())
+    }
+
+    /// Returns the resting order with id, if any
+    pub fn get_order(&self, id: OrderID) -> Option<&Order> {
+        self.order_index.get(&id)
+    }
+
+    /// Returns the position acquired by order with id, if it was ever matched
+    pub fn get_position(&self, id: OrderID) -> Option<&Position> {
+        self.positions.get(&id)
+    }
+
+    /// Returns how the market was resolved, if it was
+    pub fn get_resolution(&self) -> Option<&Resolution> {
+        self.resolution.as_ref()
+    }
+
+    /// Resolve market in favor of winning_outcome, which pays out the
+    /// full contract price per contract.
     ///
-    /// IMPORTANT: PieOrderBook will panic if you try to cancel an order that
-    /// does not exist within PieOrderBook
+    /// All resting and stop orders are cancelled and no further orders are accepted.
+    pub fn resolve(
+        &mut self,
+        winning_outcome: usize,
+    ) -> Result<Settlement<OrderID, AccountID>, errors::Resolve> {
+        if winning_outcome >= self.order_books.len() {
+            return Err(errors::Resolve::InvalidOutcome);
+        }
+
+        self.settle(Resolution::Outcome(winning_outcome))
+    }
+
+    /// Resolve market with a payout per contract for every outcome.
+    /// Payouts must not be negative and must sum to the contract price.
     ///
-    /// outcome of order is required for finding the order book where the
-    /// order exists. If the outcome is incorrect, this function will panic.
-    pub fn cancel_order(&mut self, outcome: usize, id: OrderID) {
-        self.order_books
-            .get_mut(outcome)
-            .expect("PieOrderBook::cancel_order: given outcome did not yield order_book")
-            .cancel_order(id)
-            .expect("PieOrderBook::cancel_order: error on cancel_order");
+    /// All resting and stop orders are cancelled and no further orders are accepted.
+    pub fn resolve_partial(
+        &mut self,
+        payouts: Vec<Decimal>,
+    ) -> Result<Settlement<OrderID, AccountID>, errors::Resolve> {
+        let invalid_payouts = payouts.len() != self.order_books.len()
+            || payouts.iter().any(|payout| payout.is_sign_negative())
+            || payouts.iter().sum::<Decimal>() != self.contract_price;
+
+        if invalid_payouts {
+            return Err(errors::Resolve::InvalidPayouts);
+        }
+
+        self.settle(Resolution::Partial(payouts))
+    }
+
+    /// Cancel market. Every position is refunded at the cost it was acquired for.
+    ///
+    /// All resting and stop orders are cancelled and no further orders are accepted.
+    pub fn void(&mut self) -> Result<Settlement<OrderID, AccountID>, errors::Resolve> {
+        self.settle(Resolution::Void)
+    }
+
+    fn settle(
+        &mut self,
+        resolution: Resolution,
+    ) -> Result<Settlement<OrderID, AccountID>, errors::Resolve> {
+        if self.resolution.is_some() {
+            return Err(errors::Resolve::MarketResolved);
+        }
+
+        // compute payouts before mutating anything
+        let mut payouts = Vec::new();
+        let mut account_payout_map: HashMap<AccountID, Decimal> = HashMap::new();
+        // reported in a deterministic order, so replaying a log reports the same settlement
+        for id in self.position_ids.iter() {
+            let position = &self.positions[id];
+            let amount = match resolution {
+                Resolution::Outcome(winning_outcome) if winning_outcome == position.outcome => {
+                    position.quantity.checked_mul(self.contract_price)
+                }
+                Resolution::Outcome(_) => Some(Decimal::ZERO),
+                Resolution::Partial(ref outcome_payouts) => position
+                    .quantity
+                    .checked_mul(outcome_payouts[position.outcome]),
+                Resolution::Void => Some(position.cost),
+            }
+            .ok_or(errors::Resolve::ArithmeticOverflow)?;
+
+            if let Some(account_order) = self.order_accounts.get(id) {
+                let account_payout = account_payout_map
+                    .entry(account_order.account)
+                    .or_insert(Decimal::ZERO);
+                *account_payout = account_payout
+                    .checked_add(amount)
+                    .ok_or(errors::Resolve::ArithmeticOverflow)?;
+            }
+
+            payouts.push(Payout {
+                order: *id,
+                outcome: position.outcome,
+                quantity: position.quantity,
+                amount,
+            });
+        }
+
+        // cancel all resting orders
+        let mut resting: Vec<_> = self.order_index.drain().collect();
+        resting.sort_by_key(|(_, order)| order.sequence);
+        let mut cancelled = Vec::new();
+        let mut cancelled_orders = Vec::new();
+        for (id, order) in resting {
+            self.order_books[order.outcome]
+                .cancel_order(id)
+                .expect("PieOrderBook::settle: order_index out of sync with order_books");
+            cancelled.push(id);
+            cancelled_orders.push(order);
+        }
+        self.levels.clear();
+        // stop and queued orders are never processed after resolution
+        let stop_orders = self.stop_orders.take();
+        let queued_orders = std::mem::take(&mut self.queued_orders);
+        cancelled.extend(stop_orders.iter().map(|(id, _)| *id));
+        cancelled.extend(queued_orders.iter().map(|(id, _)| *id));
+
+        // settle accounts, nothing is locked anymore
+        let mut account_payouts = Vec::new();
+        for id in self.account_ids.iter() {
+            let account = self
+                .accounts
+                .get_mut(id)
+                .expect("PieOrderBook::settle: account_ids out of sync with accounts");
+            let amount = account_payout_map.get(id).copied().unwrap_or(Decimal::ZERO);
+            // exactly one contract of every complete set wins, or it is refunded at cost
+            let amount = account
+                .sets
+                .checked_mul(self.contract_price)
+                .and_then(|sets| sets.checked_add(amount))
+                .ok_or(errors::Resolve::ArithmeticOverflow)?;
+
+            account.cash = account
+                .cash
+                .checked_add(amount)
+                .ok_or(errors::Resolve::ArithmeticOverflow)?;
+            account.locked = Decimal::ZERO;
+            account.positions.fill(Decimal::ZERO);
+            account.reserved.fill(Decimal::ZERO);
+            account.sets = Decimal::ZERO;
+
+            account_payouts.push(AccountPayout {
+                account: *id,
+                amount,
+            });
+        }
+        for account_order in self.order_accounts.values_mut() {
+            account_order.locked = Decimal::ZERO;
+            account_order.reserved = Decimal::ZERO;
+        }
+
+        self.market_state = match resolution {
+            Resolution::Void => MarketState::Voided,
+            _ => MarketState::Resolved,
+        };
+        self.resolution = Some(resolution.clone());
+
+        let mut levels = Vec::new();
+        for (id, order) in cancelled.iter().zip(cancelled_orders) {
+            levels.push((order.outcome, order.side, order.price));
+            self.emit(Event::OrderCancelled {
+                order: *id,
+                outcome: order.outcome,
+                side: order.side,
+                price: order.price,
+                quantity: order.quantity,
+            });
+        }
+        self.emit_level_changes(levels);
+        for (id, _) in stop_orders {
+            self.emit(Event::StopCancelled { order: id });
+        }
+        for (id, order) in queued_orders {
+            self.emit_queued_order_cancelled(id, &order);
+        }
+        self.emit(Event::MarketResolved(resolution.clone()));
+        self.command_log.push(match resolution {
+            Resolution::Outcome(winning_outcome) => Command::Resolve { winning_outcome },
+            Resolution::Partial(ref payouts) => Command::ResolvePartial {
+                payouts: payouts.clone(),
+            },
+            Resolution::Void => Command::Void,
+        });
+
+        Ok(Settlement {
+            resolution,
+            cancelled,
+            payouts,
+            account_payouts,
+        })
     }
 
     fn get_order_book_best_price_quantity(&self, outcome: usize, side: Side) -> (Decimal, Decimal) {
-        let res = self.order_books[outcome].get_highest_priority_price_quantity(side);
+        self.order_books[outcome]
+            .get_highest_priority_price_quantity(side)
+            .unwrap_or(self.empty_price_quantity(side))
+    }
 
+    /// (price, quantity) used in place of the best price of an empty book side
+    fn empty_price_quantity(&self, side: Side) -> (Decimal, Decimal) {
         match side {
-            Side::Buy => res.unwrap_or((Decimal::ZERO, Decimal::ZERO)),
-            Side::Sell => res.unwrap_or((self.contract_price, Decimal::ZERO)),
+            Side::Buy => (Decimal::ZERO, Decimal::ZERO),
+            Side::Sell => (self.contract_price, Decimal::ZERO),
+        }
+    }
+
+    /// Decides where an order at price matches next given the best (price, quantity)
+    /// of the own outcome order book and of the other outcome order books combined
+    fn next_match_source(
+        side: Side,
+        price: Decimal,
+        (own_price, own_quantity): (Decimal, Decimal),
+        (others_price, others_quantity): (Decimal, Decimal),
+    ) -> Option<MatchSource> {
+        let own = match side {
+            // buys match to own order book at same price
+            Side::Buy => {
+                price >= own_price && (own_price <= others_price || others_quantity.is_zero())
+            }
+            // sells match to other order books at same price
+            Side::Sell => {
+                price <= own_price && (own_price > others_price || others_quantity.is_zero())
+            }
+        } && !own_quantity.is_zero();
+
+        let others = match side {
+            Side::Buy => price >= others_price && !others_quantity.is_zero(),
+            Side::Sell => price <= others_price && !others_quantity.is_zero(),
+        };
+
+        if own {
+            Some(MatchSource::Own)
+        } else if others {
+            Some(MatchSource::Others)
+        } else {
+            None
         }
     }
 
@@ -182,7 +784,8 @@ where
         &self,
         outcome: usize,
         side: Side,
-    ) -> (Decimal, Decimal) {
+    ) -> Result<(Decimal, Decimal), errors::ProcessLimitOrder> {
+        let tradable = self.others_tradable(outcome);
         let mut price = self.contract_price;
         let mut quantity = Decimal::MAX;
 
@@ -191,46 +794,484 @@ where
                 continue;
             }
 
-            let (highest_priority_price, highest_priority_quantity) =
-                self.get_order_book_best_price_quantity(i, side.opposite());
+            let (highest_priority_price, highest_priority_quantity) = if tradable {
+                self.get_order_book_best_price_quantity(i, side.opposite())
+            } else {
+                self.empty_price_quantity(side.opposite())
+            };
 
             price = price
                 .checked_sub(highest_priority_price)
-                .expect("PieOrderBook: subtraction overflow");
+                .ok_or(errors::ProcessLimitOrder::ArithmeticOverflow)?;
 
             quantity = quantity.min(highest_priority_quantity);
         }
 
-        (price, quantity)
+        Ok((price, quantity))
+    }
+
+    /// Returns the price a post only order can rest at without matching
+    fn post_only_price(
+        &self,
+        outcome: usize,
+        side: Side,
+        price: Decimal,
+        post_only: PostOnly,
+    ) -> Result<Decimal, errors::ProcessLimitOrder> {
+        let own = self.get_order_book_best_price_quantity(outcome, side.opposite());
+        let others = self.get_other_order_books_best_price_quantity(outcome, side.opposite())?;
+
+        if Self::next_match_source(side, price, own, others).is_none() {
+            return Ok(price);
+        }
+        if let PostOnly::Reject = post_only {
+            return Err(errors::ProcessLimitOrder::PostOnlyWouldMatch);
+        }
+
+        // best opposite price out of the books that have liquidity
+        let opposite_prices = [own, others]
+            .into_iter()
+            .filter(|(_, quantity)| !quantity.is_zero())
+            .map(|(price, _)| price);
+        let step = self
+            .trading_rules
+            .tick_size
+            .ok_or(errors::ProcessLimitOrder::OrderValidationFailed)?;
+        let repriced = match side {
+            Side::Buy => opposite_prices.min().map(|best| best - step),
+            Side::Sell => opposite_prices.max().map(|best| best + step),
+        }
+        .ok_or(errors::ProcessLimitOrder::PostOnlyWouldMatch)?;
+
+        if repriced <= Decimal::ZERO || repriced >= self.contract_price {
+            return Err(errors::ProcessLimitOrder::PostOnlyWouldMatch);
+        }
+
+        Ok(repriced)
+    }
+
+    /// validation of limit orders, before they are matched or queued
+    fn validate_limit_order(
+        &self,
+        id: OrderID,
+        outcome: usize,
+        price: Decimal,
+        quantity: Decimal,
+        options: &OrderOptions<AccountID>,
+    ) -> Result<(), errors::ProcessLimitOrder> {
+        self.validate_order(id, outcome, quantity)?;
+
+        let failed_validation = price <= Decimal::ZERO || price >= self.contract_price;
+        if failed_validation {
+            return Err(errors::ProcessLimitOrder::OrderValidationFailed);
+        }
+        self.check_trading_rules(Some(price), quantity)?;
+        if self.expired(options.time_in_force) {
+            return Err(errors::ProcessLimitOrder::OrderValidationFailed);
+        }
+        // orders are repriced by one tick
+        let reprice = options.post_only == Some(PostOnly::Reprice);
+        if reprice && self.trading_rules.tick_size.is_none() {
+            return Err(errors::ProcessLimitOrder::OrderValidationFailed);
+        }
+        if let Some(display_quantity) = options.display_quantity {
+            if display_quantity <= Decimal::ZERO {
+                return Err(errors::ProcessLimitOrder::OrderValidationFailed);
+            }
+            self.check_trading_rules(None, display_quantity)?;
+        }
+
+        Ok(())
+    }
+
+    /// validation shared by all order types
+    fn validate_order(
+        &self,
+        id: OrderID,
+        outcome: usize,
+        quantity: Decimal,
+    ) -> Result<(), errors::ProcessLimitOrder> {
+        if self.resolution.is_some() {
+            return Err(errors::ProcessLimitOrder::MarketResolved);
+        }
+        if outcome >= self.order_books.len() || quantity <= Decimal::ZERO {
+            return Err(errors::ProcessLimitOrder::OrderValidationFailed);
+        }
+        let duplicate = self.order_index.contains_key(&id)
+            || self.positions.contains_key(&id)
+            || self.stop_orders.iter().any(|(stop_id, _)| *stop_id == id)
+            || self
+                .queued_orders
+                .iter()
+                .any(|(queued_id, _)| *queued_id == id);
+        if duplicate {
+            return Err(errors::ProcessLimitOrder::DuplicateOrderId);
+        }
+
+        Ok(())
+    }
+
+    /// good till time orders can not expire at or before the last expiry sweep
+    fn expired(&self, time_in_force: TimeInForce) -> bool {
+        match (time_in_force, self.expired_until) {
+            (TimeInForce::GoodTillTime(expires_at), Some(until)) => expires_at <= until,
+            _ => false,
+        }
+    }
+
+    /// matches order against own outcome and other outcome order books up to price
+    fn match_order(
+        &mut self,
+        id: OrderID,
+        outcome: usize,
+        side: Side,
+        price: Decimal,
+        quantity: Decimal,
+    ) -> Result<Execution<OrderID>, errors::ProcessLimitOrder> {
+        let mut execution = Execution {
+            matches: Vec::new(),
+            fills: Vec::new(),
+            unfilled: quantity,
+            cancelled: Vec::new(),
+            fees: Vec::new(),
+        };
+        self.match_quantity(id, outcome, side, price, &mut execution)?;
+        self.complete_execution(id, outcome, side, &mut execution)?;
+
+        Ok(execution)
+    }
+
+    /// matches the unfilled quantity of execution, adding to its matches, fills
+    /// and cancelled orders. The position and fees of order id are left to
+    /// `complete_execution`.
+    fn match_quantity(
+        &mut self,
+        id: OrderID,
+        outcome: usize,
+        side: Side,
+        price: Decimal,
+        execution: &mut Execution<OrderID>,
+    ) -> Result<(), errors::ProcessLimitOrder> {
+        // aggregated per order, in the order they were first matched
+        let mut order_matches = std::mem::take(&mut execution.matches);
+        let mut fills = std::mem::take(&mut execution.fills);
+        let mut cancelled = std::mem::take(&mut execution.cancelled);
+        let mut quantity = execution.unfilled;
+
+        while quantity > Decimal::ZERO {
+            let (own_price, own_quantity) =
+                self.get_order_book_best_price_quantity(outcome, side.opposite());
+            let (others_price, others_quantity) =
+                self.get_other_order_books_best_price_quantity(outcome, side.opposite())?;
+
+            let Some(source) = Self::next_match_source(
+                side,
+                price,
+                (own_price, own_quantity),
+                (others_price, others_quantity),
+            ) else {
+                // nothing satisfies
+                break;
+            };
+
+            let (books, maker_side) = match source {
+                MatchSource::Own => (vec![outcome], side.opposite()),
+                MatchSource::Others => (
+                    (0..self.order_books.len())
+                        .filter(|i| *i != outcome)
+                        .collect(),
+                    side,
+                ),
+            };
+            let quantity_limit = match self.prevent_self_trade(
+                id,
+                &books,
+                maker_side,
+                &mut quantity,
+                &mut cancelled,
+            )? {
+                SelfTradeAction::Match { quantity_limit } => quantity_limit,
+                SelfTradeAction::Retry => continue,
+                SelfTradeAction::Stop => break,
+            };
+
+            match source {
+                MatchSource::Own => {
+                    // match in own outcome order book
+                    let satisfied_quantity = own_quantity.min(quantity).min(quantity_limit);
+
+                    let order_match_vec = self.order_books[outcome]
+                        .process_market_order(id, side, satisfied_quantity)
+                        .map_err(|_| errors::ProcessLimitOrder::DuplicateOrderId)?;
+
+                    // makers in time priority, the last match is the taker
+                    for order_match in order_match_vec.iter().take(order_match_vec.len() - 1) {
+                        self.fill_resting_order(order_match)?;
+                        fills.push(Fill {
+                            maker: order_match.order,
+                            taker: id,
+                            outcome,
+                            price: own_price,
+                            quantity: order_match.quantity,
+                            match_type: MatchType::Direct,
+                        });
+                    }
+                    for order_match in order_match_vec {
+                        Self::add_order_match(&mut order_matches, &order_match)?
+                    }
+                    self.last_trade_prices[outcome] = Some(own_price);
+
+                    quantity = quantity
+                        .checked_sub(satisfied_quantity)
+                        .ok_or(errors::ProcessLimitOrder::ArithmeticOverflow)?;
+                }
+                MatchSource::Others => {
+                    // match in other outcome order books
+                    let satisfied_quantity = others_quantity.min(quantity).min(quantity_limit);
+                    let match_type = match side {
+                        Side::Buy => MatchType::Mint,
+                        Side::Sell => MatchType::Merge,
+                    };
+
+                    for i in 0..self.order_books.len() {
+                        if i == outcome {
+                            continue;
+                        }
+
+                        let (level_price, _) = self.get_order_book_best_price_quantity(i, side);
+                        let order_match_vec = self.order_books[i]
+                            .process_market_order(id, side.opposite(), satisfied_quantity)
+                            .map_err(|_| errors::ProcessLimitOrder::DuplicateOrderId)?;
+
+                        assert_ne!(order_match_vec.len(), 0);
+
+                        // makers in time priority, the last match is the taker
+                        for order_match in order_match_vec.iter().take(order_match_vec.len() - 1) {
+                            self.fill_resting_order(order_match)?;
+                            Self::add_order_match(&mut order_matches, order_match)?;
+                            fills.push(Fill {
+                                maker: order_match.order,
+                                taker: id,
+                                outcome: i,
+                                price: level_price,
+                                quantity: order_match.quantity,
+                                match_type,
+                            });
+                        }
+                        self.last_trade_prices[i] = Some(level_price);
+                    }
+                    self.last_trade_prices[outcome] = Some(others_price);
+
+                    let mut cost = others_price
+                        .checked_mul(satisfied_quantity)
+                        .ok_or(errors::ProcessLimitOrder::ArithmeticOverflow)?;
+                    if let Side::Sell = side {
+                        cost.set_sign_negative(true);
+                    }
+
+                    Self::add_order_match(
+                        &mut order_matches,
+                        &OrderMatch {
+                            order: id,
+                            quantity: satisfied_quantity,
+                            cost,
+                        },
+                    )?;
+
+                    quantity = quantity
+                        .checked_sub(satisfied_quantity)
+                        .ok_or(errors::ProcessLimitOrder::ArithmeticOverflow)?;
+                }
+            }
+        }
+
+        execution.matches = order_matches;
+        execution.fills = fills;
+        execution.cancelled = cancelled;
+        execution.unfilled = quantity;
+
+        Ok(())
     }
 
-    fn add_order_match_to_map(
-        map: &mut HashMap<OrderID, OrderMatch<OrderID>>,
+    /// adds the matched quantity of order id to its position and charges the
+    /// fees of every match of execution
+    fn complete_execution(
+        &mut self,
+        id: OrderID,
+        outcome: usize,
+        side: Side,
+        execution: &mut Execution<OrderID>,
+    ) -> Result<(), errors::ProcessLimitOrder> {
+        if let Some(order_match) = execution.matches.iter().find(|v| v.order == id) {
+            self.add_to_position(id, outcome, side, order_match.quantity, order_match.cost)?;
+        }
+        execution.fees = self.charge_fees(id, &execution.matches)?;
+
+        Ok(())
+    }
+
+    /// removes a resting order from its outcome order book and releases its collateral
+    fn remove_resting_order(
+        &mut self,
+        id: OrderID,
+    ) -> Result<Option<Order>, errors::ProcessLimitOrder> {
+        let Some(order) = self.order_index.remove(&id) else {
+            return Ok(None);
+        };
+
+        self.order_books[order.outcome]
+            .cancel_order(id)
+            .expect("PieOrderBook::remove_resting_order: order_index out of sync with order_books");
+        self.levels
+            .add(order.outcome, order.side, order.price, -order.quantity);
+        self.update_order_collateral(id)?;
+
+        self.emit(Event::OrderCancelled {
+            order: id,
+            outcome: order.outcome,
+            side: order.side,
+            price: order.price,
+            quantity: order.quantity,
+        });
+        self.emit_level_changes(vec![(order.outcome, order.side, order.price)]);
+
+        Ok(Some(order))
+    }
+
+    /// adds an order to its outcome order book behind all other orders at its price.
+    /// The remaining quantity of iceberg orders is split into their peak and hidden rest.
+    fn rest_order(
+        &mut self,
+        id: OrderID,
+        mut order: Order,
+    ) -> Result<(), errors::ProcessLimitOrder> {
+        let remaining = order.remaining_quantity();
+        order.quantity = order.peak.map_or(remaining, |peak| peak.min(remaining));
+        order.hidden = remaining - order.quantity;
+        order.sequence = self.next_sequence;
+        self.next_sequence += 1;
+
+        assert_eq!(
+            self.order_books[order.outcome]
+                .process_limit_order(id, order.side, order.price, order.quantity)
+                .map_err(|_| errors::ProcessLimitOrder::DuplicateOrderId)?
+                .len(),
+            0
+        );
+
+        let (outcome, side, price, quantity) =
+            (order.outcome, order.side, order.price, order.quantity);
+        self.levels.add(outcome, side, price, quantity);
+        self.track_expiry(order.expires_at);
+        self.order_index.insert(id, order);
+
+        self.emit(Event::OrderRested {
+            order: id,
+            outcome,
+            side,
+            price,
+            quantity,
+        });
+        self.emit_level_changes(vec![(outcome, side, price)]);
+
+        Ok(())
+    }
+
+    /// updates order_index and positions after a resting order was (partially) matched
+    fn fill_resting_order(
+        &mut self,
+        order_match: &OrderMatch<OrderID>,
+    ) -> Result<(), errors::ProcessLimitOrder> {
+        let Some(order) = self.order_index.get_mut(&order_match.order) else {
+            return Ok(());
+        };
+
+        order.quantity = order
+            .quantity
+            .checked_sub(order_match.quantity)
+            .ok_or(errors::ProcessLimitOrder::ArithmeticOverflow)?;
+
+        let (outcome, side) = (order.outcome, order.side);
+        self.levels
+            .add(outcome, side, order.price, -order_match.quantity);
+        if order.quantity.is_zero() && order.hidden.is_zero() {
+            self.order_index.remove(&order_match.order);
+        } else if order.quantity.is_zero() {
+            self.replenish_order(order_match.order)?;
+        }
+
+        self.add_to_position(
+            order_match.order,
+            outcome,
+            side,
+            order_match.quantity,
+            order_match.cost,
+        )?;
+        self.update_order_collateral(order_match.order)
+    }
+
+    fn add_to_position(
+        &mut self,
+        id: OrderID,
+        outcome: usize,
+        side: Side,
+        quantity: Decimal,
+        cost: Decimal,
+    ) -> Result<(), errors::ProcessLimitOrder> {
+        let position = self.positions.entry(id).or_insert_with(|| {
+            self.position_ids.push(id);
+            Position {
+                outcome,
+                quantity: Decimal::ZERO,
+                cost: Decimal::ZERO,
+            }
+        });
+
+        position.quantity = match side {
+            Side::Buy => position.quantity.checked_add(quantity),
+            Side::Sell => position.quantity.checked_sub(quantity),
+        }
+        .ok_or(errors::ProcessLimitOrder::ArithmeticOverflow)?;
+
+        position.cost = position
+            .cost
+            .checked_add(cost)
+            .ok_or(errors::ProcessLimitOrder::ArithmeticOverflow)?;
+
+        self.apply_fill_to_account(id, outcome, side, quantity, cost)
+    }
+
+    fn add_order_match(
+        order_matches: &mut Vec<OrderMatch<OrderID>>,
         order_match: &OrderMatch<OrderID>,
-    ) {
-        match map.get_mut(&order_match.order) {
-            Some(map_entry) => {
-                map_entry.quantity = map_entry
+    ) -> Result<(), errors::ProcessLimitOrder> {
+        match order_matches
+            .iter_mut()
+            .find(|entry| entry.order == order_match.order)
+        {
+            Some(entry) => {
+                entry.quantity = entry
                     .quantity
                     .checked_add(order_match.quantity)
-                    .expect("PieOrderBook: addition overflow");
+                    .ok_or(errors::ProcessLimitOrder::ArithmeticOverflow)?;
 
-                map_entry.cost = map_entry
+                entry.cost = entry
                     .cost
                     .checked_add(order_match.cost)
-                    .expect("PieOrderBook: addition overflow");
+                    .ok_or(errors::ProcessLimitOrder::ArithmeticOverflow)?;
             }
 
-            None => {
-                map.insert(order_match.order, order_match.clone());
-            }
+            None => order_matches.push(order_match.clone()),
         }
+
+        Ok(())
     }
 }
 
-impl<OrderID> Display for PieOrderBook<OrderID>
+impl<OrderID, AccountID> Display for PieOrderBook<OrderID, AccountID>
 where
     OrderID: Copy + PartialEq + Eq + Hash + Display,
+    AccountID: Copy + PartialEq + Eq + Hash,
 {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         writeln!(f, "Contract Price: {}", self.contract_price)?;
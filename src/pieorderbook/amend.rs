@@ -0,0 +1,215 @@
+use std::hash::Hash;
+
+use rust_decimal::Decimal;
+
+use crate::{command::Command, errors, event::Event, execution::Execution, order::Order};
+
+use super::{same_side, Admission, PieOrderBook};
+
+impl<OrderID, AccountID> PieOrderBook<OrderID, AccountID>
+where
+    OrderID: Copy + PartialEq + Eq + Hash,
+    AccountID: Copy + PartialEq + Eq + Hash,
+{
+    /// Amend the price and quantity of a resting order
+    ///
+    /// Reducing the quantity at the same price keeps the time priority of the order.
+    /// The quantity of iceberg orders includes their hidden rest, which is reduced first.
+    /// Any other amendment moves the order behind all orders at its new price and
+    /// matches it like a new good till cancel limit order, including against the
+    /// other outcome order books. Post only orders are rejected or repriced at their
+    /// new price like new post only orders.
+    ///
+    /// While the market or the outcome is not open, orders can only be reduced.
+    ///
+    /// Orders placed on behalf of an account must have enough collateral for the
+    /// amended order, otherwise the order is left unchanged. An order that expired
+    /// by the current time of the order book clock is expired instead of amended.
+    pub fn amend_order(
+        &mut self,
+        id: OrderID,
+        price: Decimal,
+        quantity: Decimal,
+    ) -> Result<Execution<OrderID>, errors::AmendOrder> {
+        self.amend_order_at(self.now(), id, price, quantity)
+    }
+
+    pub(super) fn amend_order_at(
+        &mut self,
+        now: u64,
+        id: OrderID,
+        price: Decimal,
+        quantity: Decimal,
+    ) -> Result<Execution<OrderID>, errors::AmendOrder> {
+        self.expire_stale_orders(now)?;
+        if self.resolution.is_some() {
+            return Err(errors::AmendOrder::MarketResolved);
+        }
+        let order = self
+            .order_index
+            .get(&id)
+            .cloned()
+            .ok_or(errors::AmendOrder::UnknownOrder)?;
+        let admission = self.admission(order.outcome)?;
+
+        let failed_validation =
+            price <= Decimal::ZERO || price >= self.contract_price || quantity <= Decimal::ZERO;
+        if failed_validation {
+            return Err(errors::AmendOrder::OrderValidationFailed);
+        }
+        self.check_trading_rules(Some(price), quantity)?;
+
+        let (outcome, side) = (order.outcome, order.side);
+        let reduced = price == order.price && quantity <= order.remaining_quantity();
+        // amended orders that would match can not be queued
+        if let (Admission::Queue, false) = (admission, reduced) {
+            return Err(errors::AmendOrder::MarketNotOpen);
+        }
+        // post only orders stay post only at their new price
+        let price = match order.post_only {
+            Some(post_only) if !reduced => self.post_only_price(outcome, side, price, post_only)?,
+            _ => price,
+        };
+
+        let amended = |pie_ob: &mut Self| {
+            pie_ob.emit(Event::OrderAmended {
+                order: id,
+                outcome,
+                side,
+                price,
+                quantity,
+            });
+            pie_ob.emit_level_changes(vec![(outcome, side, order.price)]);
+        };
+
+        if reduced {
+            // the hidden rest of iceberg orders is reduced first
+            let displayed = quantity.min(order.quantity);
+            self.order_index
+                .get_mut(&id)
+                .expect("PieOrderBook::amend_order: unknown order")
+                .hidden = quantity - displayed;
+            self.reduce_order(id, displayed)?;
+            amended(self);
+            self.command_log.push(Command::AmendOrder {
+                id,
+                price,
+                quantity,
+                now,
+            });
+
+            return Ok(Execution {
+                matches: Vec::new(),
+                fills: Vec::new(),
+                unfilled: quantity,
+                cancelled: Vec::new(),
+                fees: Vec::new(),
+            });
+        }
+
+        // release the collateral of the resting order before checking the amended one
+        let saved = self.order_accounts.get(&id).map(|account_order| {
+            (
+                account_order.clone(),
+                self.accounts[&account_order.account].clone(),
+            )
+        });
+        self.order_index.remove(&id);
+        self.update_order_collateral(id)?;
+        if let Some((account_order, account)) = saved {
+            match self.check_collateral(account_order.account, outcome, side, price, quantity) {
+                Ok(reserved) => self.add_account_order(
+                    id,
+                    account_order.account,
+                    outcome,
+                    reserved,
+                    account_order.self_trade_prevention,
+                )?,
+                Err(e) => {
+                    self.accounts.insert(account_order.account, account);
+                    self.order_accounts.insert(id, account_order);
+                    self.order_index.insert(id, order);
+                    return Err(e.into());
+                }
+            }
+        }
+        self.order_books[outcome]
+            .cancel_order(id)
+            .expect("PieOrderBook::amend_order: order_index out of sync with order_books");
+        self.levels.add(outcome, side, order.price, -order.quantity);
+        amended(self);
+
+        // process amended order
+        let execution = self.match_order(id, outcome, side, price, quantity)?;
+        self.emit_execution(side, &execution);
+        if !execution.unfilled.is_zero() && !execution.cancelled.contains(&id) {
+            let order = Order {
+                price,
+                quantity: execution.unfilled,
+                hidden: Decimal::ZERO,
+                ..order
+            };
+            self.rest_order(id, order)?;
+        }
+        self.update_order_collateral(id)?;
+        self.command_log.push(Command::AmendOrder {
+            id,
+            price,
+            quantity,
+            now,
+        });
+        self.trigger_stop_orders();
+
+        Ok(execution)
+    }
+
+    /// reduces the quantity of a resting order while keeping its time priority
+    pub(super) fn reduce_order(
+        &mut self,
+        id: OrderID,
+        quantity: Decimal,
+    ) -> Result<(), errors::ProcessLimitOrder> {
+        let order = self
+            .order_index
+            .get_mut(&id)
+            .expect("PieOrderBook::reduce_order: unknown order");
+        let reduction = order.quantity - quantity;
+        order.quantity = quantity;
+        let order = order.clone();
+        self.levels
+            .add(order.outcome, order.side, order.price, -reduction);
+
+        // orders can not be reduced inside the outcome order book, so the order
+        // and every order behind it at the same price are added again in time priority
+        let mut requeued: Vec<_> = self
+            .order_index
+            .iter()
+            .filter(|(_, other)| {
+                other.outcome == order.outcome
+                    && other.price == order.price
+                    && other.sequence >= order.sequence
+                    && same_side(other.side, order.side)
+            })
+            .map(|(other_id, other)| (*other_id, other.sequence, other.quantity))
+            .collect();
+        requeued.sort_by_key(|(_, sequence, _)| *sequence);
+
+        let order_book = &mut self.order_books[order.outcome];
+        for (other_id, _, _) in requeued.iter() {
+            order_book
+                .cancel_order(*other_id)
+                .expect("PieOrderBook::reduce_order: order_index out of sync with order_books");
+        }
+        for (other_id, _, other_quantity) in requeued {
+            assert_eq!(
+                order_book
+                    .process_limit_order(other_id, order.side, order.price, other_quantity)
+                    .map_err(|_| errors::ProcessLimitOrder::DuplicateOrderId)?
+                    .len(),
+                0
+            );
+        }
+
+        self.update_order_collateral(id)
+    }
+}
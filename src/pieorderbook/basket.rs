@@ -0,0 +1,425 @@
+use std::hash::Hash;
+
+use rust_decimal::Decimal;
+use rust_ob::{OrderMatch, Side};
+
+use crate::{
+    command::Command,
+    errors,
+    execution::{Execution, Fill, MatchType},
+    order::OrderOptions,
+};
+
+use super::{Admission, MatchSource, PieOrderBook, SelfTradeAction};
+
+impl<OrderID, AccountID> PieOrderBook<OrderID, AccountID>
+where
+    OrderID: Copy + PartialEq + Eq + Hash,
+    AccountID: Copy + PartialEq + Eq + Hash,
+{
+    /// Process a basket order that buys or sells quantity of every outcome of a
+    /// set at once, e.g. for exposure to "outcome 1 or outcome 2". Only one
+    /// outcome wins, so a basket pays out contract_price if any of its outcomes wins.
+    ///
+    /// Every leg is an outcome with the id of its order and is matched like a
+    /// market order, against its own outcome order book and the other outcome
+    /// order books. The basket as a whole is also matched against the outcomes
+    /// that are not in it, e.g. buying outcomes 1 and 2 mints complete sets with
+    /// bids of outcome 0, whichever is better.
+    ///
+    /// The basket is all or nothing: it is simulated on a copy of the order book
+    /// first and only processed if every leg fills completely and the combined
+    /// price per basket is not worse than price. Otherwise every leg is dropped
+    /// without any matches.
+    ///
+    /// Returns the execution of every leg, in the order of legs.
+    pub fn process_basket_order(
+        &mut self,
+        legs: Vec<(OrderID, usize)>,
+        side: Side,
+        price: Decimal,
+        quantity: Decimal,
+        options: OrderOptions<AccountID>,
+    ) -> Result<Vec<Execution<OrderID>>, errors::ProcessBasketOrder> {
+        self.process_basket_order_at(self.now(), legs, side, price, quantity, options)
+    }
+
+    pub(super) fn process_basket_order_at(
+        &mut self,
+        now: u64,
+        legs: Vec<(OrderID, usize)>,
+        side: Side,
+        price: Decimal,
+        quantity: Decimal,
+        options: OrderOptions<AccountID>,
+    ) -> Result<Vec<Execution<OrderID>>, errors::ProcessBasketOrder> {
+        self.expire_stale_orders(now)?;
+
+        // order parameter validation
+        if self.resolution.is_some() {
+            return Err(errors::ProcessBasketOrder::MarketResolved);
+        }
+        let outcomes = self.order_books.len();
+        let mut covered = vec![false; outcomes];
+        for (i, (id, outcome)) in legs.iter().enumerate() {
+            if *outcome >= outcomes || covered[*outcome] {
+                return Err(errors::ProcessBasketOrder::OrderValidationFailed);
+            }
+            covered[*outcome] = true;
+            if legs[..i].iter().any(|(other_id, _)| other_id == id) {
+                return Err(errors::ProcessBasketOrder::DuplicateOrderId);
+            }
+        }
+        // a basket of every outcome is a complete set, see mint_complete_set
+        let failed_validation = legs.is_empty()
+            || legs.len() == outcomes
+            || price <= Decimal::ZERO
+            || price >= self.contract_price;
+        if failed_validation {
+            return Err(errors::ProcessBasketOrder::OrderValidationFailed);
+        }
+        self.check_trading_rules(Some(price), quantity)?;
+        // all or nothing orders can not be queued
+        for (_, outcome) in legs.iter() {
+            if let Admission::Queue = self.admission(*outcome)? {
+                return Err(errors::ProcessBasketOrder::MarketNotOpen);
+            }
+        }
+
+        let command = Command::BasketOrder {
+            legs: legs.clone(),
+            side,
+            price,
+            quantity,
+            options: options.clone(),
+            now,
+        };
+
+        let mut simulation = self.simulation();
+        let simulated = simulation.execute_basket(&legs, side, price, quantity, &options)?;
+
+        let filled = Self::basket_filled(&legs, side, price, quantity, &simulated)?;
+        // legs are accepted together, so their collateral is only known after matching
+        let overdrawn = options.account.is_some_and(|account| {
+            simulation
+                .get_account(account)
+                .is_some_and(|account| account.available() < Decimal::ZERO)
+        });
+        if filled && overdrawn {
+            return Err(errors::ProcessBasketOrder::InsufficientCollateral);
+        }
+
+        let executions = if filled {
+            self.execute_basket(&legs, side, price, quantity, &options)?
+        } else {
+            legs.iter()
+                .map(|_| Execution {
+                    matches: Vec::new(),
+                    fills: Vec::new(),
+                    unfilled: quantity,
+                    cancelled: Vec::new(),
+                    fees: Vec::new(),
+                })
+                .collect()
+        };
+        self.command_log.push(command);
+        self.trigger_stop_orders();
+
+        Ok(executions)
+    }
+
+    /// Matches every leg of a basket, in chunks of whole baskets from the better of
+    /// matching every leg on its own and matching the basket as a whole against the
+    /// outcomes that are not in it.
+    fn execute_basket(
+        &mut self,
+        legs: &[(OrderID, usize)],
+        side: Side,
+        price: Decimal,
+        quantity: Decimal,
+        options: &OrderOptions<AccountID>,
+    ) -> Result<Vec<Execution<OrderID>>, errors::ProcessMarketOrder> {
+        // buy legs never match above the basket price
+        let worst_price = match side {
+            Side::Buy => Some(price),
+            Side::Sell => None,
+        };
+        let mut leg_prices = Vec::new();
+        for (id, outcome) in legs.iter() {
+            leg_prices.push(self.accept_market_order(
+                *id,
+                *outcome,
+                side,
+                quantity,
+                worst_price,
+                options,
+            )?);
+        }
+
+        let mut executions: Vec<_> = legs
+            .iter()
+            .map(|_| Execution {
+                matches: Vec::new(),
+                fills: Vec::new(),
+                unfilled: quantity,
+                cancelled: Vec::new(),
+                fees: Vec::new(),
+            })
+            .collect();
+        let outside: Vec<usize> = (0..self.order_books.len())
+            .filter(|i| legs.iter().all(|(_, outcome)| outcome != i))
+            .collect();
+
+        let mut quantity = quantity;
+        while quantity > Decimal::ZERO {
+            let legs_source = self.legs_price_quantity(legs, &leg_prices, side)?;
+            let complement_source = self.complement_price_quantity(&outside, side, price)?;
+            let complement = match (legs_source, complement_source) {
+                (Some(_), None) => false,
+                (None, Some(_)) => true,
+                // legs are preferred at the same price
+                (Some((legs_price, _)), Some((complement_price, _))) => match side {
+                    Side::Buy => complement_price < legs_price,
+                    Side::Sell => complement_price > legs_price,
+                },
+                // nothing satisfies
+                (None, None) => break,
+            };
+
+            if complement {
+                let (complement_price, complement_quantity) = complement_source
+                    .expect("PieOrderBook::execute_basket: complement without liquidity");
+                let (taker, _) = legs[0];
+                let quantity_limit = match self.prevent_self_trade(
+                    taker,
+                    &outside,
+                    side,
+                    &mut quantity,
+                    &mut executions[0].cancelled,
+                )? {
+                    SelfTradeAction::Match { quantity_limit } => quantity_limit,
+                    SelfTradeAction::Retry => continue,
+                    SelfTradeAction::Stop => break,
+                };
+
+                let satisfied_quantity = complement_quantity.min(quantity).min(quantity_limit);
+                self.match_complement(
+                    legs,
+                    &outside,
+                    side,
+                    complement_price,
+                    satisfied_quantity,
+                    &mut executions,
+                )?;
+                quantity -= satisfied_quantity;
+            } else {
+                let (_, legs_quantity) =
+                    legs_source.expect("PieOrderBook::execute_basket: legs without liquidity");
+                let satisfied_quantity = legs_quantity.min(quantity);
+
+                let mut filled = true;
+                for (((id, outcome), leg_price), execution) in
+                    legs.iter().zip(&leg_prices).zip(executions.iter_mut())
+                {
+                    let mut chunk = Execution {
+                        matches: std::mem::take(&mut execution.matches),
+                        fills: std::mem::take(&mut execution.fills),
+                        unfilled: satisfied_quantity,
+                        cancelled: std::mem::take(&mut execution.cancelled),
+                        fees: Vec::new(),
+                    };
+                    self.match_quantity(*id, *outcome, side, *leg_price, &mut chunk)?;
+
+                    filled &= chunk.unfilled.is_zero();
+                    execution.unfilled -= satisfied_quantity - chunk.unfilled;
+                    execution.matches = chunk.matches;
+                    execution.fills = chunk.fills;
+                    execution.cancelled = chunk.cancelled;
+                }
+                if !filled {
+                    break;
+                }
+                quantity -= satisfied_quantity;
+            }
+        }
+
+        for ((id, outcome), execution) in legs.iter().zip(executions.iter_mut()) {
+            self.complete_execution(*id, *outcome, side, execution)?;
+            self.emit_execution(side, execution);
+            self.update_order_collateral(*id)?;
+        }
+
+        Ok(executions)
+    }
+
+    /// Returns the (price, quantity) of the next basket matched leg by leg, each
+    /// leg against its own outcome order book or the other outcome order books
+    fn legs_price_quantity(
+        &self,
+        legs: &[(OrderID, usize)],
+        leg_prices: &[Decimal],
+        side: Side,
+    ) -> Result<Option<(Decimal, Decimal)>, errors::ProcessLimitOrder> {
+        let mut price = Decimal::ZERO;
+        let mut quantity = Decimal::MAX;
+
+        for ((_, outcome), leg_price) in legs.iter().zip(leg_prices) {
+            let own = self.get_order_book_best_price_quantity(*outcome, side.opposite());
+            let others =
+                self.get_other_order_books_best_price_quantity(*outcome, side.opposite())?;
+            let (level_price, level_quantity) =
+                match Self::next_match_source(side, *leg_price, own, others) {
+                    Some(MatchSource::Own) => own,
+                    Some(MatchSource::Others) => others,
+                    None => return Ok(None),
+                };
+
+            price = price
+                .checked_add(level_price)
+                .ok_or(errors::ProcessLimitOrder::ArithmeticOverflow)?;
+            quantity = quantity.min(level_quantity);
+        }
+
+        Ok(Some((price, quantity)))
+    }
+
+    /// Returns the (price, quantity) of the next basket matched as a whole against
+    /// the outside outcome order books: buys mint complete sets with their bids,
+    /// sells merge complete sets with their asks. None if it is worse than price.
+    fn complement_price_quantity(
+        &self,
+        outside: &[usize],
+        side: Side,
+        price: Decimal,
+    ) -> Result<Option<(Decimal, Decimal)>, errors::ProcessLimitOrder> {
+        if outside
+            .iter()
+            .any(|outcome| self.is_outcome_halted(*outcome))
+        {
+            return Ok(None);
+        }
+
+        let mut complement_price = self.contract_price;
+        let mut quantity = Decimal::MAX;
+        for outcome in outside.iter() {
+            let (level_price, level_quantity) =
+                self.get_order_book_best_price_quantity(*outcome, side);
+            complement_price = complement_price
+                .checked_sub(level_price)
+                .ok_or(errors::ProcessLimitOrder::ArithmeticOverflow)?;
+            quantity = quantity.min(level_quantity);
+        }
+
+        let satisfies = match side {
+            Side::Buy => complement_price <= price,
+            Side::Sell => complement_price >= price,
+        };
+        if !satisfies || quantity.is_zero() {
+            return Ok(None);
+        }
+
+        Ok(Some((complement_price, quantity)))
+    }
+
+    /// Matches quantity baskets against the best level of every outside outcome
+    /// order book. The fills of the outside orders are reported with the first leg,
+    /// the cost of the baskets is split evenly over the legs.
+    fn match_complement(
+        &mut self,
+        legs: &[(OrderID, usize)],
+        outside: &[usize],
+        side: Side,
+        price: Decimal,
+        quantity: Decimal,
+        executions: &mut [Execution<OrderID>],
+    ) -> Result<(), errors::ProcessLimitOrder> {
+        let (taker, _) = legs[0];
+        let match_type = match side {
+            Side::Buy => MatchType::Mint,
+            Side::Sell => MatchType::Merge,
+        };
+
+        for outcome in outside.iter() {
+            let (level_price, _) = self.get_order_book_best_price_quantity(*outcome, side);
+            let order_match_vec = self.order_books[*outcome]
+                .process_market_order(taker, side.opposite(), quantity)
+                .map_err(|_| errors::ProcessLimitOrder::DuplicateOrderId)?;
+
+            // makers in time priority, the last match is the taker
+            for order_match in order_match_vec.iter().take(order_match_vec.len() - 1) {
+                self.fill_resting_order(order_match)?;
+                Self::add_order_match(&mut executions[0].matches, order_match)?;
+                executions[0].fills.push(Fill {
+                    maker: order_match.order,
+                    taker,
+                    outcome: *outcome,
+                    price: level_price,
+                    quantity: order_match.quantity,
+                    match_type,
+                });
+            }
+            self.last_trade_prices[*outcome] = Some(level_price);
+        }
+
+        let mut cost = price
+            .checked_mul(quantity)
+            .ok_or(errors::ProcessLimitOrder::ArithmeticOverflow)?;
+        if let Side::Sell = side {
+            cost.set_sign_negative(true);
+        }
+        // the first leg takes the rounding remainder
+        let leg_cost = cost
+            .checked_div(Decimal::from(legs.len()))
+            .ok_or(errors::ProcessLimitOrder::ArithmeticOverflow)?;
+        let first_leg_cost = cost - leg_cost * Decimal::from(legs.len() - 1);
+
+        for (i, ((id, _), execution)) in legs.iter().zip(executions.iter_mut()).enumerate() {
+            Self::add_order_match(
+                &mut execution.matches,
+                &OrderMatch {
+                    order: *id,
+                    quantity,
+                    cost: if i == 0 { first_leg_cost } else { leg_cost },
+                },
+            )?;
+            execution.unfilled -= quantity;
+        }
+
+        Ok(())
+    }
+
+    /// whether every leg filled completely within the basket price
+    fn basket_filled(
+        legs: &[(OrderID, usize)],
+        side: Side,
+        price: Decimal,
+        quantity: Decimal,
+        executions: &[Execution<OrderID>],
+    ) -> Result<bool, errors::ProcessBasketOrder> {
+        let mut cost = Decimal::ZERO;
+        for ((id, _), execution) in legs.iter().zip(executions) {
+            let Some(leg_match) = execution
+                .matches
+                .iter()
+                .find(|order_match| order_match.order == *id)
+            else {
+                return Ok(false);
+            };
+            if leg_match.quantity != quantity {
+                return Ok(false);
+            }
+            cost = cost
+                .checked_add(leg_match.cost)
+                .ok_or(errors::ProcessBasketOrder::ArithmeticOverflow)?;
+        }
+
+        let limit = price
+            .checked_mul(quantity)
+            .ok_or(errors::ProcessBasketOrder::ArithmeticOverflow)?;
+        // sells have negative costs
+        Ok(match side {
+            Side::Buy => cost <= limit,
+            Side::Sell => -cost >= limit,
+        })
+    }
+}
@@ -0,0 +1,175 @@
+use std::hash::Hash;
+
+use crate::{
+    command::{Command, CommandOutput},
+    errors,
+};
+
+use super::PieOrderBook;
+
+impl<OrderID, AccountID> PieOrderBook<OrderID, AccountID>
+where
+    OrderID: Copy + PartialEq + Eq + Hash,
+    AccountID: Copy + PartialEq + Eq + Hash,
+{
+    /// Reconstruct a `PieOrderBook` from a command log, which must start with `Command::New`.
+    /// Replaying the log of an order book reproduces its state and the outputs of every command.
+    pub fn replay(
+        commands: impl IntoIterator<Item = Command<OrderID, AccountID>>,
+    ) -> Result<Self, errors::Replay> {
+        let mut commands = commands.into_iter();
+
+        let mut pie_ob = match commands.next() {
+            Some(Command::New {
+                contract_price,
+                outcomes,
+            }) => Self::with_accounts(contract_price, outcomes).map_err(errors::Replay::New)?,
+            _ => return Err(errors::Replay::MissingNew),
+        };
+
+        for (index, command) in commands.enumerate() {
+            pie_ob
+                .apply(command)
+                .map_err(|error| errors::Replay::Apply {
+                    index: index + 1,
+                    error,
+                })?;
+        }
+
+        Ok(pie_ob)
+    }
+
+    /// Apply a command to the order book as if the corresponding operation was called
+    pub fn apply(
+        &mut self,
+        command: Command<OrderID, AccountID>,
+    ) -> Result<CommandOutput<OrderID, AccountID>, errors::Apply> {
+        use errors::Apply as E;
+
+        match command {
+            Command::New { .. } => Err(E::UnexpectedNew),
+            Command::Deposit { account, amount } => self
+                .deposit(account, amount)
+                .map(|_| CommandOutput::None)
+                .map_err(E::Deposit),
+            Command::Withdraw { account, amount } => self
+                .withdraw(account, amount)
+                .map(|_| CommandOutput::None)
+                .map_err(E::Withdraw),
+            Command::MintCompleteSet { account, quantity } => self
+                .mint_complete_set(account, quantity)
+                .map(|_| CommandOutput::None)
+                .map_err(E::MintCompleteSet),
+            Command::MergeCompleteSet { account, quantity } => self
+                .merge_complete_set(account, quantity)
+                .map(|_| CommandOutput::None)
+                .map_err(E::MergeCompleteSet),
+            Command::LimitOrder {
+                id,
+                outcome,
+                side,
+                price,
+                quantity,
+                options,
+                now,
+            } => self
+                .process_limit_order_at(now, id, outcome, side, price, quantity, options)
+                .map(CommandOutput::Execution)
+                .map_err(E::ProcessLimitOrder),
+            Command::MarketOrder {
+                id,
+                outcome,
+                side,
+                quantity,
+                worst_price,
+                options,
+                now,
+            } => self
+                .process_market_order_at(now, id, outcome, side, quantity, worst_price, options)
+                .map(CommandOutput::Execution)
+                .map_err(E::ProcessMarketOrder),
+            Command::BasketOrder {
+                legs,
+                side,
+                price,
+                quantity,
+                options,
+                now,
+            } => self
+                .process_basket_order_at(now, legs, side, price, quantity, options)
+                .map(CommandOutput::Basket)
+                .map_err(E::ProcessBasketOrder),
+            Command::CancelOrder { id } => self
+                .cancel_order(id)
+                .map(|_| CommandOutput::None)
+                .map_err(E::CancelOrder),
+            Command::AmendOrder {
+                id,
+                price,
+                quantity,
+                now,
+            } => self
+                .amend_order_at(now, id, price, quantity)
+                .map(CommandOutput::Execution)
+                .map_err(E::AmendOrder),
+            Command::SubmitStopOrder { id, order, now } => self
+                .submit_stop_order_at(now, id, order)
+                .map(|_| CommandOutput::None)
+                .map_err(E::SubmitStopOrder),
+            Command::CancelStopOrder { id } => self
+                .cancel_stop_order(id)
+                .map(|_| CommandOutput::None)
+                .map_err(E::CancelOrder),
+            Command::ExpireOrders { now } => self
+                .expire_orders(now)
+                .map(CommandOutput::Expired)
+                .map_err(E::ExpireOrders),
+            Command::Resolve { winning_outcome } => self
+                .resolve(winning_outcome)
+                .map(CommandOutput::Settlement)
+                .map_err(E::Resolve),
+            Command::ResolvePartial { payouts } => self
+                .resolve_partial(payouts)
+                .map(CommandOutput::Settlement)
+                .map_err(E::Resolve),
+            Command::Void => self
+                .void()
+                .map(CommandOutput::Settlement)
+                .map_err(E::Resolve),
+            Command::SetTradingRules { rules } => self
+                .set_trading_rules(rules)
+                .map(|_| CommandOutput::None)
+                .map_err(E::SetTradingRules),
+            Command::SetFeeSchedule { schedule } => self
+                .set_fee_schedule(schedule)
+                .map(|_| CommandOutput::None)
+                .map_err(E::SetFeeSchedule),
+            Command::SetMarketState { state } => self
+                .set_market_state(state)
+                .map(|_| CommandOutput::None)
+                .map_err(E::SetMarketState),
+            Command::HaltOutcome { outcome } => self
+                .halt_outcome(outcome)
+                .map(|_| CommandOutput::None)
+                .map_err(E::HaltOutcome),
+            Command::ResumeOutcome { outcome } => self
+                .resume_outcome(outcome)
+                .map(|_| CommandOutput::None)
+                .map_err(E::HaltOutcome),
+        }
+    }
+
+    /// Returns every successful state changing operation so far, starting with `Command::New`.
+    /// The log of a restored order book starts after its snapshot, applying it to the
+    /// restored snapshot reproduces the order book.
+    pub fn command_log(&self) -> &[Command<OrderID, AccountID>] {
+        &self.command_log
+    }
+
+    /// Removes and returns the command log, so it does not grow without bound.
+    /// Take it together with a `snapshot`, the commands logged afterwards reproduce
+    /// the order book when applied to the restored snapshot.
+    pub fn take_command_log(&mut self) -> Vec<Command<OrderID, AccountID>> {
+        std::mem::take(&mut self.command_log)
+    }
+}
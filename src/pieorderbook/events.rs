@@ -0,0 +1,90 @@
+use std::{fmt::Debug, hash::Hash};
+
+use rust_decimal::Decimal;
+use rust_ob::Side;
+
+use crate::{execution::MatchType, Event, EventSink, Execution};
+
+use super::{same_side, PieOrderBook};
+
+pub(super) struct BoxedEventSink<OrderID, AccountID>(Box<dyn EventSink<OrderID, AccountID>>);
+
+impl<OrderID, AccountID> Debug for BoxedEventSink<OrderID, AccountID> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("EventSink")
+    }
+}
+
+impl<OrderID, AccountID> PieOrderBook<OrderID, AccountID>
+where
+    OrderID: Copy + PartialEq + Eq + Hash,
+    AccountID: Copy + PartialEq + Eq + Hash,
+{
+    /// Set the sink every following state change is emitted to,
+    /// replacing the previous one
+    pub fn set_event_sink(&mut self, sink: impl EventSink<OrderID, AccountID> + 'static) {
+        self.event_sink = Some(BoxedEventSink(Box::new(sink)));
+    }
+
+    /// Stop emitting events
+    pub fn clear_event_sink(&mut self) {
+        self.event_sink = None;
+    }
+
+    pub(super) fn emit(&mut self, event: Event<OrderID, AccountID>) {
+        if let Some(BoxedEventSink(sink)) = self.event_sink.as_mut() {
+            sink.emit(event);
+        }
+    }
+
+    /// Emits the fills of an order on side and the resulting changes of the maker price levels
+    pub(super) fn emit_execution(&mut self, side: Side, execution: &Execution<OrderID>) {
+        if self.event_sink.is_none() {
+            return;
+        }
+
+        let mut levels = Vec::new();
+        for fill in execution.fills.iter() {
+            let maker_side = match fill.match_type {
+                MatchType::Direct => side.opposite(),
+                MatchType::Mint => Side::Buy,
+                MatchType::Merge => Side::Sell,
+            };
+            levels.push((fill.outcome, maker_side, fill.price));
+
+            self.emit(Event::Fill(fill.clone()));
+        }
+
+        self.emit_level_changes(levels);
+    }
+
+    /// Emits the current quantity of every distinct (outcome, side, price) level
+    pub(super) fn emit_level_changes(&mut self, levels: Vec<(usize, Side, Decimal)>) {
+        if self.event_sink.is_none() {
+            return;
+        }
+
+        let same_level = |(o1, s1, p1): &(usize, Side, Decimal),
+                          (o2, s2, p2): &(usize, Side, Decimal)| {
+            o1 == o2 && p1 == p2 && same_side(*s1, *s2)
+        };
+
+        let mut emitted: Vec<(usize, Side, Decimal)> = Vec::new();
+        for level in levels {
+            if emitted.iter().any(|other| same_level(other, &level)) {
+                continue;
+            }
+
+            let (outcome, side, price) = level;
+            let quantity = self.levels.get(outcome, side, price);
+
+            self.emit(Event::BookLevelChanged {
+                outcome,
+                side,
+                price,
+                quantity,
+            });
+            emitted.push(level);
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use std::{fmt::Debug, hash::Hash};
+
+use crate::{clock::Clock, command::Command, errors, event::Event, order::TimeInForce};
+
+use super::PieOrderBook;
+
+pub(super) struct BoxedClock(pub(super) Box<dyn Clock>);
+
+impl Debug for BoxedClock {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Clock")
+    }
+}
+
+impl<OrderID, AccountID> PieOrderBook<OrderID, AccountID>
+where
+    OrderID: Copy + PartialEq + Eq + Hash,
+    AccountID: Copy + PartialEq + Eq + Hash,
+{
+    /// Set the clock used by `expire_due_orders`, replacing the previous one
+    pub fn set_clock(&mut self, clock: impl Clock + 'static) {
+        self.clock = BoxedClock(Box::new(clock));
+    }
+
+    /// Returns the current time of the order book clock
+    pub fn now(&self) -> u64 {
+        self.clock.0.now()
+    }
+
+    /// Returns the time of the last expiry sweep, if any.
+    /// Good till time orders expiring at or before it are rejected.
+    pub fn get_expired_until(&self) -> Option<u64> {
+        self.expired_until
+    }
+
+    /// Cancel every resting, stop and queued order with a good till time at or before
+    /// now, across all outcomes. Returns the expired orders in time priority,
+    /// followed by the expired stop orders and queued orders in the order they were
+    /// submitted.
+    ///
+    /// The sweep is logged with now, so replaying it does not depend on the clock.
+    pub fn expire_orders(&mut self, now: u64) -> Result<Vec<OrderID>, errors::ExpireOrders> {
+        if self.resolution.is_some() {
+            return Err(errors::ExpireOrders::MarketResolved);
+        }
+
+        let mut expired: Vec<_> = self
+            .order_index
+            .iter()
+            .filter(|(_, order)| order.expires_at.is_some_and(|expires_at| expires_at <= now))
+            .map(|(id, order)| (*id, order.sequence))
+            .collect();
+        expired.sort_by_key(|(_, sequence)| *sequence);
+        let mut expired: Vec<_> = expired.into_iter().map(|(id, _)| id).collect();
+        for id in expired.iter() {
+            self.remove_resting_order(*id)
+                .map_err(|_| errors::ExpireOrders::ArithmeticOverflow)?;
+        }
+
+        let expired_stops =
+            self.stop_orders
+                .remove_unless(|order| match order.options.time_in_force {
+                    TimeInForce::GoodTillTime(expires_at) => expires_at > now,
+                    _ => true,
+                });
+        for id in expired_stops {
+            self.emit(Event::StopCancelled { order: id });
+            expired.push(id);
+        }
+
+        self.expire_queued_orders(now, &mut expired)?;
+
+        self.expired_until = Some(self.expired_until.map_or(now, |until| until.max(now)));
+        self.earliest_expiry = self.next_expiry();
+        self.command_log.push(Command::ExpireOrders { now });
+        self.trigger_stop_orders();
+
+        Ok(expired)
+    }
+
+    /// Expire orders at the current time of the order book clock
+    pub fn expire_due_orders(&mut self) -> Result<Vec<OrderID>, errors::ExpireOrders> {
+        self.expire_orders(self.now())
+    }
+
+    /// Expires orders before an order is processed at now, so orders that expired
+    /// by then never match. The sweep is logged before the order.
+    pub(super) fn expire_stale_orders(
+        &mut self,
+        now: u64,
+    ) -> Result<(), errors::ProcessLimitOrder> {
+        let stale = self
+            .earliest_expiry
+            .is_some_and(|expires_at| expires_at <= now);
+        if stale && self.resolution.is_none() {
+            self.expire_orders(now)?;
+        }
+
+        Ok(())
+    }
+
+    /// good till time orders processed at now must expire after it
+    pub(super) fn expires_by(time_in_force: TimeInForce, now: u64) -> bool {
+        time_in_force
+            .expires_at()
+            .is_some_and(|expires_at| expires_at <= now)
+    }
+
+    /// keeps earliest_expiry at or before the expiry of an added order
+    pub(super) fn track_expiry(&mut self, expires_at: Option<u64>) {
+        if let Some(expires_at) = expires_at {
+            self.earliest_expiry = Some(
+                self.earliest_expiry
+                    .map_or(expires_at, |earliest| earliest.min(expires_at)),
+            );
+        }
+    }
+
+    /// earliest expiry of every resting, stop and queued order
+    pub(super) fn next_expiry(&self) -> Option<u64> {
+        let resting = self
+            .order_index
+            .values()
+            .filter_map(|order| order.expires_at);
+        let stops = self
+            .stop_orders
+            .iter()
+            .filter_map(|(_, order)| order.options.time_in_force.expires_at());
+        let queued = self
+            .queued_orders
+            .iter()
+            .filter_map(|(_, order)| order.options.time_in_force.expires_at());
+
+        resting.chain(stops).chain(queued).min()
+    }
+}
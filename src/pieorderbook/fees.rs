@@ -0,0 +1,139 @@
+use std::hash::Hash;
+
+use rust_decimal::Decimal;
+use rust_ob::OrderMatch;
+
+use crate::{
+    command::Command,
+    errors,
+    fee::{FeeCharge, FeeSchedule, Liquidity},
+};
+
+use super::PieOrderBook;
+
+impl<OrderID, AccountID> PieOrderBook<OrderID, AccountID>
+where
+    OrderID: Copy + PartialEq + Eq + Hash,
+    AccountID: Copy + PartialEq + Eq + Hash,
+{
+    /// Set the fees charged for following matches, or stop charging fees.
+    ///
+    /// The highest maker fee resting orders can be charged is locked as collateral,
+    /// so the schedule is rejected if an account can not cover it.
+    pub fn set_fee_schedule(
+        &mut self,
+        schedule: Option<FeeSchedule>,
+    ) -> Result<(), errors::SetFeeSchedule> {
+        let unsorted = schedule.as_ref().is_some_and(|schedule| {
+            schedule
+                .tiers
+                .windows(2)
+                .any(|tiers| tiers[0].min_volume >= tiers[1].min_volume)
+        });
+        if unsorted {
+            return Err(errors::SetFeeSchedule::UnsortedTiers);
+        }
+        if schedule
+            .as_ref()
+            .is_some_and(FeeSchedule::has_flat_maker_fee)
+        {
+            return Err(errors::SetFeeSchedule::FlatMakerFee);
+        }
+
+        let previous = std::mem::replace(&mut self.fee_schedule, schedule.clone());
+        let covered = self
+            .update_collateral()
+            .map_err(|_| errors::SetFeeSchedule::ArithmeticOverflow)?;
+        if !covered {
+            self.fee_schedule = previous;
+            self.update_collateral()
+                .map_err(|_| errors::SetFeeSchedule::ArithmeticOverflow)?;
+            return Err(errors::SetFeeSchedule::InsufficientCollateral);
+        }
+
+        self.command_log.push(Command::SetFeeSchedule { schedule });
+
+        Ok(())
+    }
+
+    /// Returns the fees charged for matches, if any
+    pub fn get_fee_schedule(&self) -> Option<&FeeSchedule> {
+        self.fee_schedule.as_ref()
+    }
+
+    /// Returns the sum of all fees charged so far
+    pub fn get_collected_fees(&self) -> Decimal {
+        self.collected_fees
+    }
+
+    /// re-computes the collateral of every order placed on behalf of an account.
+    /// Returns whether every account still covers its locked collateral.
+    fn update_collateral(&mut self) -> Result<bool, errors::ProcessLimitOrder> {
+        let ids: Vec<OrderID> = self.order_accounts.keys().copied().collect();
+        for id in ids {
+            self.update_order_collateral(id)?;
+        }
+
+        Ok(self
+            .accounts
+            .values()
+            .all(|account| account.available() >= Decimal::ZERO))
+    }
+
+    /// Charges the fee of every match of an execution of order taker.
+    /// Fees are deducted from the cash of the accounts the orders were placed on
+    /// behalf of, whose volume grows by the matched quantity.
+    pub(super) fn charge_fees(
+        &mut self,
+        taker: OrderID,
+        order_matches: &[OrderMatch<OrderID>],
+    ) -> Result<Vec<FeeCharge<OrderID>>, errors::ProcessLimitOrder> {
+        let mut fees = Vec::new();
+
+        for order_match in order_matches {
+            let liquidity = if order_match.order == taker {
+                Liquidity::Taker
+            } else {
+                Liquidity::Maker
+            };
+            let mut account = self
+                .order_accounts
+                .get(&order_match.order)
+                .and_then(|account_order| self.accounts.get_mut(&account_order.account));
+
+            if let Some(schedule) = self.fee_schedule.as_ref() {
+                let volume = account.as_ref().map_or(Decimal::ZERO, |a| a.volume);
+                let amount = schedule
+                    .rate(liquidity, volume)
+                    .fee(order_match.quantity, order_match.cost)
+                    .ok_or(errors::ProcessLimitOrder::ArithmeticOverflow)?;
+
+                if let Some(account) = account.as_mut() {
+                    account.cash = account
+                        .cash
+                        .checked_sub(amount)
+                        .ok_or(errors::ProcessLimitOrder::ArithmeticOverflow)?;
+                }
+                self.collected_fees = self
+                    .collected_fees
+                    .checked_add(amount)
+                    .ok_or(errors::ProcessLimitOrder::ArithmeticOverflow)?;
+
+                fees.push(FeeCharge {
+                    order: order_match.order,
+                    liquidity,
+                    amount,
+                });
+            }
+
+            if let Some(account) = account {
+                account.volume = account
+                    .volume
+                    .checked_add(order_match.quantity)
+                    .ok_or(errors::ProcessLimitOrder::ArithmeticOverflow)?;
+            }
+        }
+
+        Ok(fees)
+    }
+}
@@ -0,0 +1,48 @@
+use std::hash::Hash;
+
+use crate::{errors, event::Event};
+
+use super::PieOrderBook;
+
+impl<OrderID, AccountID> PieOrderBook<OrderID, AccountID>
+where
+    OrderID: Copy + PartialEq + Eq + Hash,
+    AccountID: Copy + PartialEq + Eq + Hash,
+{
+    /// moves the next peak of an iceberg order, whose displayed quantity is no longer
+    /// in its outcome order book, from its hidden rest behind all orders at its price
+    pub(super) fn replenish_order(&mut self, id: OrderID) -> Result<(), errors::ProcessLimitOrder> {
+        let order = self
+            .order_index
+            .get_mut(&id)
+            .expect("PieOrderBook::replenish_order: unknown order");
+
+        let peak = order.peak.unwrap_or(order.hidden).min(order.hidden);
+        order.quantity = peak;
+        order.hidden -= peak;
+        order.sequence = self.next_sequence;
+        self.next_sequence += 1;
+        let order = order.clone();
+
+        assert_eq!(
+            self.order_books[order.outcome]
+                .process_limit_order(id, order.side, order.price, order.quantity)
+                .map_err(|_| errors::ProcessLimitOrder::DuplicateOrderId)?
+                .len(),
+            0
+        );
+        self.levels
+            .add(order.outcome, order.side, order.price, order.quantity);
+
+        self.emit(Event::OrderReplenished {
+            order: id,
+            outcome: order.outcome,
+            side: order.side,
+            price: order.price,
+            quantity: order.quantity,
+        });
+        self.emit_level_changes(vec![(order.outcome, order.side, order.price)]);
+
+        Ok(())
+    }
+}
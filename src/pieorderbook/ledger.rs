@@ -0,0 +1,414 @@
+use std::hash::Hash;
+
+use rust_decimal::Decimal;
+use rust_ob::Side;
+
+use crate::{
+    account::Account, command::Command, errors, event::Event, fee::Liquidity,
+    order::SelfTradePrevention,
+};
+
+use super::PieOrderBook;
+
+/// Account an order was placed on behalf of
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct AccountOrder<AccountID> {
+    pub(super) account: AccountID,
+    pub(super) outcome: usize,
+    /// collateral currently locked for the resting part of the order
+    pub(super) locked: Decimal,
+    /// contracts currently reserved to cover the resting part of a sell order
+    pub(super) reserved: Decimal,
+    pub(super) self_trade_prevention: Option<SelfTradePrevention>,
+}
+
+impl<OrderID, AccountID> PieOrderBook<OrderID, AccountID>
+where
+    OrderID: Copy + PartialEq + Eq + Hash,
+    AccountID: Copy + PartialEq + Eq + Hash,
+{
+    /// Deposit amount into account. The account is created if it does not exist yet.
+    pub fn deposit(&mut self, account: AccountID, amount: Decimal) -> Result<(), errors::Deposit> {
+        if amount <= Decimal::ZERO {
+            return Err(errors::Deposit::NonPositiveAmount);
+        }
+        let command = Command::Deposit { account, amount };
+
+        let outcomes = self.order_books.len();
+        let account_ids = &mut self.account_ids;
+        let account_entry = self.accounts.entry(account).or_insert_with(|| {
+            account_ids.push(account);
+            Account::new(outcomes)
+        });
+
+        account_entry.cash = account_entry
+            .cash
+            .checked_add(amount)
+            .ok_or(errors::Deposit::ArithmeticOverflow)?;
+        self.emit(Event::Deposited { account, amount });
+        self.command_log.push(command);
+
+        Ok(())
+    }
+
+    /// Withdraw amount from account. Locked collateral can not be withdrawn.
+    pub fn withdraw(
+        &mut self,
+        account: AccountID,
+        amount: Decimal,
+    ) -> Result<(), errors::Withdraw> {
+        if amount <= Decimal::ZERO {
+            return Err(errors::Withdraw::NonPositiveAmount);
+        }
+        let command = Command::Withdraw { account, amount };
+
+        let account_entry = self
+            .accounts
+            .get_mut(&account)
+            .ok_or(errors::Withdraw::UnknownAccount)?;
+
+        if amount > account_entry.available() {
+            return Err(errors::Withdraw::InsufficientFunds);
+        }
+
+        account_entry.cash -= amount;
+        self.emit(Event::Withdrawn { account, amount });
+        self.command_log.push(command);
+
+        Ok(())
+    }
+
+    /// Mint quantity complete sets for account: one contract of every outcome
+    /// per set, paid for with contract_price per set. The contracts cover
+    /// short positions, which may release locked collateral.
+    pub fn mint_complete_set(
+        &mut self,
+        account: AccountID,
+        quantity: Decimal,
+    ) -> Result<(), errors::MintCompleteSet> {
+        if self.resolution.is_some() {
+            return Err(errors::MintCompleteSet::MarketResolved);
+        }
+        if quantity <= Decimal::ZERO {
+            return Err(errors::MintCompleteSet::NonPositiveQuantity);
+        }
+        if !Self::on_grid(quantity, self.trading_rules.lot_size) {
+            return Err(errors::MintCompleteSet::QuantityOffLot);
+        }
+        let command = Command::MintCompleteSet { account, quantity };
+
+        let mut minted = self
+            .accounts
+            .get(&account)
+            .cloned()
+            .ok_or(errors::MintCompleteSet::UnknownAccount)?;
+        let cost = self
+            .contract_price
+            .checked_mul(quantity)
+            .ok_or(errors::MintCompleteSet::ArithmeticOverflow)?;
+        self.move_complete_sets(&mut minted, quantity, -cost)
+            .ok_or(errors::MintCompleteSet::ArithmeticOverflow)?;
+
+        if minted.cash < minted.locked {
+            return Err(errors::MintCompleteSet::InsufficientFunds);
+        }
+
+        self.accounts.insert(account, minted);
+        self.emit(Event::CompleteSetsMinted { account, quantity });
+        self.command_log.push(command);
+
+        Ok(())
+    }
+
+    /// Merge quantity complete sets of account: one held contract of every
+    /// outcome per set is given up for contract_price per set. Contracts
+    /// reserved for resting sell orders can not be merged.
+    pub fn merge_complete_set(
+        &mut self,
+        account: AccountID,
+        quantity: Decimal,
+    ) -> Result<(), errors::MergeCompleteSet> {
+        if self.resolution.is_some() {
+            return Err(errors::MergeCompleteSet::MarketResolved);
+        }
+        if quantity <= Decimal::ZERO {
+            return Err(errors::MergeCompleteSet::NonPositiveQuantity);
+        }
+        if !Self::on_grid(quantity, self.trading_rules.lot_size) {
+            return Err(errors::MergeCompleteSet::QuantityOffLot);
+        }
+        let command = Command::MergeCompleteSet { account, quantity };
+
+        let mut merged = self
+            .accounts
+            .get(&account)
+            .cloned()
+            .ok_or(errors::MergeCompleteSet::UnknownAccount)?;
+        let insufficient = merged
+            .positions
+            .iter()
+            .zip(merged.reserved.iter())
+            .any(|(position, reserved)| *position - *reserved < quantity);
+        if insufficient {
+            return Err(errors::MergeCompleteSet::InsufficientContracts);
+        }
+
+        let proceeds = self
+            .contract_price
+            .checked_mul(quantity)
+            .ok_or(errors::MergeCompleteSet::ArithmeticOverflow)?;
+        self.move_complete_sets(&mut merged, -quantity, proceeds)
+            .ok_or(errors::MergeCompleteSet::ArithmeticOverflow)?;
+
+        self.accounts.insert(account, merged);
+        self.emit(Event::CompleteSetsMerged { account, quantity });
+        self.command_log.push(command);
+
+        Ok(())
+    }
+
+    /// adds sets to every outcome position and cash to account,
+    /// re-computing the collateral locked for its positions
+    fn move_complete_sets(
+        &self,
+        account: &mut Account,
+        sets: Decimal,
+        cash: Decimal,
+    ) -> Option<()> {
+        let old_margin = account.margin(self.contract_price)?;
+
+        account.cash = account.cash.checked_add(cash)?;
+        account.sets = account.sets.checked_add(sets)?;
+        for position in account.positions.iter_mut() {
+            *position = position.checked_add(sets)?;
+        }
+
+        let new_margin = account.margin(self.contract_price)?;
+        account.locked = account
+            .locked
+            .checked_sub(old_margin)?
+            .checked_add(new_margin)?;
+
+        Some(())
+    }
+
+    /// Returns account, if it exists
+    pub fn get_account(&self, account: AccountID) -> Option<&Account> {
+        self.accounts.get(&account)
+    }
+
+    /// Checks that account has enough available collateral for the worst case
+    /// loss of a new order: price * quantity for buys and
+    /// (contract_price - price) * quantity for the part of sells that is not
+    /// covered by contracts the account holds, plus the highest taker and maker
+    /// fees the order can be charged, as fees are deducted from cash.
+    ///
+    /// Returns the quantity of the order covered by held contracts.
+    pub(super) fn check_collateral(
+        &self,
+        account: AccountID,
+        outcome: usize,
+        side: Side,
+        price: Decimal,
+        quantity: Decimal,
+    ) -> Result<Decimal, errors::ProcessLimitOrder> {
+        let account = self
+            .accounts
+            .get(&account)
+            .ok_or(errors::ProcessLimitOrder::UnknownAccount)?;
+
+        let covered = match side {
+            Side::Buy => Decimal::ZERO,
+            Side::Sell => account.positions[outcome]
+                .checked_sub(account.reserved[outcome])
+                .ok_or(errors::ProcessLimitOrder::ArithmeticOverflow)?
+                .max(Decimal::ZERO)
+                .min(quantity),
+        };
+
+        let max_loss = match side {
+            Side::Buy => price.checked_mul(quantity),
+            Side::Sell => (self.contract_price - price).checked_mul(quantity - covered),
+        }
+        .ok_or(errors::ProcessLimitOrder::ArithmeticOverflow)?;
+
+        // an order is charged one taker fee per execution, sells match at up to contract_price
+        let max_price = match side {
+            Side::Buy => price,
+            Side::Sell => self.contract_price,
+        };
+        let max_taker_fee = self.max_fee(Liquidity::Taker, account.volume, max_price, quantity)?;
+        let max_maker_fee = self.max_fee(Liquidity::Maker, account.volume, price, quantity)?;
+        let max_loss = max_loss
+            .checked_add(max_taker_fee)
+            .and_then(|max_loss| max_loss.checked_add(max_maker_fee))
+            .ok_or(errors::ProcessLimitOrder::ArithmeticOverflow)?;
+
+        if max_loss > account.available() {
+            return Err(errors::ProcessLimitOrder::InsufficientCollateral);
+        }
+
+        Ok(covered)
+    }
+
+    /// highest fee of liquidity an account with volume can be charged for
+    /// matching quantity contracts at price
+    fn max_fee(
+        &self,
+        liquidity: Liquidity,
+        volume: Decimal,
+        price: Decimal,
+        quantity: Decimal,
+    ) -> Result<Decimal, errors::ProcessLimitOrder> {
+        let Some(schedule) = self.fee_schedule.as_ref() else {
+            return Ok(Decimal::ZERO);
+        };
+
+        price
+            .checked_mul(quantity)
+            .and_then(|cost| schedule.max_fee(liquidity, volume, quantity, cost))
+            .ok_or(errors::ProcessLimitOrder::ArithmeticOverflow)
+    }
+
+    /// records that order id is placed on behalf of account, reserving the
+    /// held contracts that cover it
+    pub(super) fn add_account_order(
+        &mut self,
+        id: OrderID,
+        account: AccountID,
+        outcome: usize,
+        reserved: Decimal,
+        self_trade_prevention: Option<SelfTradePrevention>,
+    ) -> Result<(), errors::ProcessLimitOrder> {
+        let account_entry = self
+            .accounts
+            .get_mut(&account)
+            .ok_or(errors::ProcessLimitOrder::UnknownAccount)?;
+
+        account_entry.reserved[outcome] = account_entry.reserved[outcome]
+            .checked_add(reserved)
+            .ok_or(errors::ProcessLimitOrder::ArithmeticOverflow)?;
+
+        let account_order = AccountOrder {
+            account,
+            outcome,
+            locked: Decimal::ZERO,
+            reserved,
+            self_trade_prevention,
+        };
+        if self.order_accounts.insert(id, account_order).is_none() {
+            self.order_account_ids.push(id);
+        }
+
+        Ok(())
+    }
+
+    /// applies a fill of order id to the account it was placed on behalf of, if any
+    pub(super) fn apply_fill_to_account(
+        &mut self,
+        id: OrderID,
+        outcome: usize,
+        side: Side,
+        quantity: Decimal,
+        cost: Decimal,
+    ) -> Result<(), errors::ProcessLimitOrder> {
+        let Some(account_order) = self.order_accounts.get(&id) else {
+            return Ok(());
+        };
+        let account = self
+            .accounts
+            .get_mut(&account_order.account)
+            .expect("PieOrderBook::apply_fill_to_account: order placed by unknown account");
+
+        let old_margin = account
+            .margin(self.contract_price)
+            .ok_or(errors::ProcessLimitOrder::ArithmeticOverflow)?;
+
+        account.cash = account
+            .cash
+            .checked_sub(cost)
+            .ok_or(errors::ProcessLimitOrder::ArithmeticOverflow)?;
+        account.positions[outcome] = match side {
+            Side::Buy => account.positions[outcome].checked_add(quantity),
+            Side::Sell => account.positions[outcome].checked_sub(quantity),
+        }
+        .ok_or(errors::ProcessLimitOrder::ArithmeticOverflow)?;
+
+        let new_margin = account
+            .margin(self.contract_price)
+            .ok_or(errors::ProcessLimitOrder::ArithmeticOverflow)?;
+
+        account.locked = account
+            .locked
+            .checked_sub(old_margin)
+            .and_then(|locked| locked.checked_add(new_margin))
+            .ok_or(errors::ProcessLimitOrder::ArithmeticOverflow)?;
+
+        Ok(())
+    }
+
+    /// re-computes collateral locked and contracts reserved for order id
+    /// from its resting or queued quantity
+    pub(super) fn update_order_collateral(
+        &mut self,
+        id: OrderID,
+    ) -> Result<(), errors::ProcessLimitOrder> {
+        let Some(account_order) = self.order_accounts.get(&id) else {
+            return Ok(());
+        };
+
+        let open = match self.order_index.get(&id) {
+            Some(order) => Some((order.side, order.price, order.remaining_quantity())),
+            None => self
+                .queued_orders
+                .iter()
+                .find(|(queued_id, _)| *queued_id == id)
+                .map(|(_, order)| (order.side, order.price, order.quantity)),
+        };
+        let (locked, reserved) = match open {
+            Some((side, price, quantity)) => {
+                // fills use up the reserved contracts first
+                let reserved = account_order.reserved.min(quantity);
+                let locked = match side {
+                    Side::Buy => price.checked_mul(quantity),
+                    Side::Sell => (self.contract_price - price).checked_mul(quantity - reserved),
+                }
+                .ok_or(errors::ProcessLimitOrder::ArithmeticOverflow)?;
+                let volume = self.accounts[&account_order.account].volume;
+                let max_maker_fee = self.max_fee(Liquidity::Maker, volume, price, quantity)?;
+                let locked = locked
+                    .checked_add(max_maker_fee)
+                    .ok_or(errors::ProcessLimitOrder::ArithmeticOverflow)?;
+
+                (locked, reserved)
+            }
+            None => (Decimal::ZERO, Decimal::ZERO),
+        };
+
+        let account_order = self
+            .order_accounts
+            .get_mut(&id)
+            .expect("PieOrderBook::update_order_collateral: order_accounts changed");
+
+        let account = self
+            .accounts
+            .get_mut(&account_order.account)
+            .expect("PieOrderBook::update_order_collateral: order placed by unknown account");
+
+        account.locked = account
+            .locked
+            .checked_sub(account_order.locked)
+            .and_then(|account_locked| account_locked.checked_add(locked))
+            .ok_or(errors::ProcessLimitOrder::ArithmeticOverflow)?;
+        account.reserved[account_order.outcome] = account.reserved[account_order.outcome]
+            .checked_sub(account_order.reserved)
+            .and_then(|account_reserved| account_reserved.checked_add(reserved))
+            .ok_or(errors::ProcessLimitOrder::ArithmeticOverflow)?;
+
+        account_order.locked = locked;
+        account_order.reserved = reserved;
+
+        Ok(())
+    }
+}
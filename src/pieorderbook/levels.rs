@@ -0,0 +1,455 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    hash::Hash,
+};
+
+use rust_decimal::Decimal;
+use rust_ob::Side;
+
+use crate::{
+    errors,
+    order::{OrderOptions, SelfTradePrevention},
+    Depth, OutcomeProbability, PriceLevel, Probabilities, Quote,
+};
+
+use super::{same_side, MatchSource, PieOrderBook};
+
+impl<OrderID, AccountID> PieOrderBook<OrderID, AccountID>
+where
+    OrderID: Copy + PartialEq + Eq + Hash,
+    AccountID: Copy + PartialEq + Eq + Hash,
+{
+    /// Returns up to levels aggregated price levels of each side of an outcome order book.
+    /// Returns `None` if the outcome does not exist.
+    pub fn depth(&self, outcome: usize, levels: usize) -> Option<Depth> {
+        if outcome >= self.order_books.len() {
+            return None;
+        }
+
+        let side_levels = |side: Side| {
+            self.book_levels(outcome, side)
+                .into_iter()
+                .take(levels)
+                .map(|(price, quantity)| PriceLevel { price, quantity })
+                .collect()
+        };
+
+        Some(Depth {
+            bids: side_levels(Side::Buy),
+            asks: side_levels(Side::Sell),
+        })
+    }
+
+    /// Same as `depth`, but includes the liquidity implied by the other outcome order books.
+    /// Bids of all other outcomes imply an ask at the contract price minus their sum,
+    /// and asks of all other outcomes imply a bid in the same way.
+    pub fn effective_depth(&self, outcome: usize, levels: usize) -> Option<Depth> {
+        if outcome >= self.order_books.len() {
+            return None;
+        }
+
+        let side_levels = |side: Side| {
+            let mut merged = self.book_levels(outcome, side);
+            merged.extend(self.implied_levels(outcome, side));
+            merged.sort_by(|(p1, _), (p2, _)| match side {
+                Side::Buy => p2.cmp(p1),
+                Side::Sell => p1.cmp(p2),
+            });
+
+            let mut result: Vec<PriceLevel> = Vec::new();
+            for (price, quantity) in merged {
+                match result.last_mut() {
+                    Some(level) if level.price == price => level.quantity += quantity,
+                    _ => result.push(PriceLevel { price, quantity }),
+                }
+            }
+            result.truncate(levels);
+            result
+        };
+
+        Some(Depth {
+            bids: side_levels(Side::Buy),
+            asks: side_levels(Side::Sell),
+        })
+    }
+
+    /// Returns the direct, implied and effective top of book of an outcome.
+    /// Returns `None` if the outcome does not exist.
+    pub fn quote(&self, outcome: usize) -> Option<Quote> {
+        if outcome >= self.order_books.len() {
+            return None;
+        }
+
+        let level = |(price, quantity): (Decimal, Decimal)| PriceLevel { price, quantity };
+        let direct = |side: Side| self.book_levels(outcome, side).first().copied().map(level);
+        let implied = |side: Side| {
+            self.implied_levels(outcome, side)
+                .first()
+                .copied()
+                .map(level)
+        };
+        let best = |side: Side, direct: Option<PriceLevel>, implied: Option<PriceLevel>| match (
+            direct, implied,
+        ) {
+            (Some(direct), Some(implied)) if direct.price == implied.price => Some(PriceLevel {
+                price: direct.price,
+                quantity: direct.quantity + implied.quantity,
+            }),
+            (Some(direct), Some(implied)) => {
+                let direct_better = match side {
+                    Side::Buy => direct.price > implied.price,
+                    Side::Sell => direct.price < implied.price,
+                };
+                Some(if direct_better { direct } else { implied })
+            }
+            (direct, implied) => direct.or(implied),
+        };
+
+        let (bid, ask) = (direct(Side::Buy), direct(Side::Sell));
+        let (implied_bid, implied_ask) = (implied(Side::Buy), implied(Side::Sell));
+
+        Some(Quote {
+            outcome,
+            bid,
+            ask,
+            implied_bid,
+            implied_ask,
+            best_bid: best(Side::Buy, bid, implied_bid),
+            best_ask: best(Side::Sell, ask, implied_ask),
+        })
+    }
+
+    /// Returns the quote of every outcome
+    pub fn quotes(&self) -> Vec<Quote> {
+        (0..self.order_books.len())
+            .filter_map(|outcome| self.quote(outcome))
+            .collect()
+    }
+
+    /// Returns the probabilities implied by the mid price of every outcome
+    pub fn probabilities(&self) -> Probabilities {
+        let mut outcomes: Vec<OutcomeProbability> = self
+            .quotes()
+            .into_iter()
+            .map(|quote| {
+                let bid = quote.best_bid.map_or(Decimal::ZERO, |level| level.price);
+                let ask = quote
+                    .best_ask
+                    .map_or(self.contract_price, |level| level.price);
+                let mid = (bid + ask) / Decimal::TWO;
+
+                OutcomeProbability {
+                    outcome: quote.outcome,
+                    mid,
+                    raw: mid / self.contract_price,
+                    normalized: Decimal::ZERO,
+                }
+            })
+            .collect();
+
+        let total: Decimal = outcomes.iter().map(|outcome| outcome.raw).sum();
+        // every mid price is positive, so the sum is never zero
+        for outcome in outcomes.iter_mut() {
+            outcome.normalized = outcome.raw / total;
+        }
+
+        Probabilities {
+            outcomes,
+            overround: total - Decimal::ONE,
+        }
+    }
+
+    /// Returns (price, quantity) of the price levels of an outcome book side
+    /// implied by the opposite sides of the other outcome order books,
+    /// ordered from best to worst price
+    fn implied_levels(&self, outcome: usize, side: Side) -> Vec<(Decimal, Decimal)> {
+        if !self.others_tradable(outcome) {
+            return Vec::new();
+        }
+
+        let mut others: Vec<VecDeque<(Decimal, Decimal)>> = (0..self.order_books.len())
+            .filter(|i| *i != outcome)
+            .map(|i| self.book_levels(i, side.opposite()).into())
+            .collect();
+
+        let mut levels: Vec<(Decimal, Decimal)> = Vec::new();
+
+        while others.iter().all(|levels| !levels.is_empty()) {
+            let mut price = self.contract_price;
+            let mut quantity = Decimal::MAX;
+            for (level_price, level_quantity) in others.iter().filter_map(VecDeque::front) {
+                price -= level_price;
+                quantity = quantity.min(*level_quantity);
+            }
+
+            // deeper levels only imply worse prices
+            if price <= Decimal::ZERO || price >= self.contract_price {
+                break;
+            }
+
+            for other in others.iter_mut() {
+                let level = other
+                    .front_mut()
+                    .expect("PieOrderBook::implied_levels: empty level");
+                level.1 -= quantity;
+                if level.1.is_zero() {
+                    other.pop_front();
+                }
+            }
+
+            match levels.last_mut() {
+                Some((last_price, last_quantity)) if *last_price == price => {
+                    *last_quantity += quantity
+                }
+                _ => levels.push((price, quantity)),
+            }
+        }
+
+        levels
+    }
+
+    /// Returns (price, quantity) of every price level of a book side,
+    /// ordered from best to worst price
+    pub(super) fn book_levels(&self, outcome: usize, side: Side) -> Vec<(Decimal, Decimal)> {
+        self.levels.iter(outcome, side).collect()
+    }
+
+    /// Returns the quantity an order would be filled up to price, without
+    /// mutating anything. Hidden iceberg quantity refills behind its price level.
+    /// Self trade prevention skips the resting orders of the account it cancels
+    /// and stops at the ones that would cancel or decrement the order.
+    pub(super) fn fillable_quantity(
+        &self,
+        outcome: usize,
+        side: Side,
+        price: Decimal,
+        mut quantity: Decimal,
+        options: &OrderOptions<AccountID>,
+    ) -> Result<Decimal, errors::ProcessLimitOrder> {
+        // own outcome book side the order matches against, and the other
+        // outcome book sides it matches together with
+        let book_sides: Vec<Side> = (0..self.order_books.len())
+            .map(|i| if i == outcome { side.opposite() } else { side })
+            .collect();
+        let tradable = self.others_tradable(outcome);
+        let prevention = options.account.zip(options.self_trade_prevention);
+        let mut books: Vec<VecDeque<SimulatedOrder>> = book_sides
+            .iter()
+            .enumerate()
+            .map(|(i, book_side)| {
+                if i == outcome || tradable {
+                    self.simulated_orders(i, *book_side, prevention.map(|(account, _)| account))
+                } else {
+                    VecDeque::new()
+                }
+            })
+            .collect();
+
+        let mut filled = Decimal::ZERO;
+
+        while quantity > Decimal::ZERO {
+            let best = |i: usize, books: &Vec<VecDeque<SimulatedOrder>>| match books[i].front() {
+                Some(front) => (
+                    front.price,
+                    books[i]
+                        .iter()
+                        .take_while(|order| order.price == front.price)
+                        .map(|order| order.quantity)
+                        .sum(),
+                ),
+                None => self.empty_price_quantity(book_sides[i]),
+            };
+
+            let own = best(outcome, &books);
+            let mut others = (self.contract_price, Decimal::MAX);
+            for i in 0..books.len() {
+                if i == outcome {
+                    continue;
+                }
+
+                let (level_price, level_quantity) = best(i, &books);
+                others.0 = others
+                    .0
+                    .checked_sub(level_price)
+                    .ok_or(errors::ProcessLimitOrder::ArithmeticOverflow)?;
+                others.1 = others.1.min(level_quantity);
+            }
+
+            let matched_books: Vec<usize> = match Self::next_match_source(side, price, own, others)
+            {
+                Some(MatchSource::Own) => vec![outcome],
+                Some(MatchSource::Others) => (0..books.len()).filter(|i| *i != outcome).collect(),
+                None => break,
+            };
+
+            // self trade prevention looks at the highest priority order of every matched book
+            let self_owned: Vec<usize> = matched_books
+                .iter()
+                .copied()
+                .filter(|i| books[*i].front().is_some_and(|order| order.owned))
+                .collect();
+            if !self_owned.is_empty() {
+                match prevention {
+                    Some((_, SelfTradePrevention::CancelOldest)) => {
+                        for i in self_owned {
+                            books[i].pop_front();
+                        }
+                        continue;
+                    }
+                    // the order is cancelled or decremented without filling
+                    _ => break,
+                }
+            }
+
+            let satisfied_quantity = matched_books
+                .iter()
+                .filter_map(|i| books[*i].front())
+                .map(|order| order.quantity)
+                .fold(quantity, Decimal::min);
+            for i in matched_books {
+                Self::fill_simulated_order(&mut books[i], satisfied_quantity);
+            }
+
+            quantity -= satisfied_quantity;
+            filled += satisfied_quantity;
+        }
+
+        Ok(filled)
+    }
+
+    /// Returns the resting orders of a book side in priority, marking the ones of account
+    fn simulated_orders(
+        &self,
+        outcome: usize,
+        side: Side,
+        account: Option<AccountID>,
+    ) -> VecDeque<SimulatedOrder> {
+        let mut orders: Vec<_> = self
+            .order_index
+            .iter()
+            .filter(|(_, order)| order.outcome == outcome && same_side(order.side, side))
+            .collect();
+        orders.sort_by(|(_, o1), (_, o2)| {
+            match side {
+                Side::Buy => o2.price.cmp(&o1.price),
+                Side::Sell => o1.price.cmp(&o2.price),
+            }
+            .then(o1.sequence.cmp(&o2.sequence))
+        });
+
+        orders
+            .into_iter()
+            .map(|(id, order)| SimulatedOrder {
+                price: order.price,
+                quantity: order.quantity,
+                hidden: order.hidden,
+                peak: order.peak,
+                owned: account.is_some_and(|account| {
+                    self.order_accounts
+                        .get(id)
+                        .is_some_and(|account_order| account_order.account == account)
+                }),
+            })
+            .collect()
+    }
+
+    /// fills the highest priority order of book by quantity, replenishing icebergs
+    /// behind the other orders at their price
+    fn fill_simulated_order(book: &mut VecDeque<SimulatedOrder>, quantity: Decimal) {
+        let front = book
+            .front_mut()
+            .expect("PieOrderBook::fill_simulated_order: matched empty book");
+        front.quantity -= quantity;
+        if !front.quantity.is_zero() {
+            return;
+        }
+
+        let mut order = book
+            .pop_front()
+            .expect("PieOrderBook::fill_simulated_order: matched empty book");
+        if order.hidden.is_zero() {
+            return;
+        }
+        order.quantity = order.peak.unwrap_or(order.hidden).min(order.hidden);
+        order.hidden -= order.quantity;
+        let index = book
+            .iter()
+            .position(|other| other.price != order.price)
+            .unwrap_or(book.len());
+        book.insert(index, order);
+    }
+}
+
+/// Resting order as seen by `fillable_quantity`
+struct SimulatedOrder {
+    price: Decimal,
+    quantity: Decimal,
+    hidden: Decimal,
+    peak: Option<Decimal>,
+    // placed on behalf of the account of the simulated order
+    owned: bool,
+}
+
+/// Displayed quantity of every price level of the outcome order books,
+/// updated whenever a resting order is added, filled, reduced or removed
+#[derive(Debug)]
+pub(super) struct PriceLevels {
+    // price -> quantity of the bids and asks of every outcome
+    sides: Vec<[BTreeMap<Decimal, Decimal>; 2]>,
+}
+
+impl PriceLevels {
+    pub(super) fn new(outcomes: usize) -> Self {
+        PriceLevels {
+            sides: (0..outcomes).map(|_| Default::default()).collect(),
+        }
+    }
+
+    fn slot(side: Side) -> usize {
+        match side {
+            Side::Buy => 0,
+            Side::Sell => 1,
+        }
+    }
+
+    /// adds quantity to a price level, removing the level once it is empty.
+    /// Negative quantities are taken out of the level.
+    pub(super) fn add(&mut self, outcome: usize, side: Side, price: Decimal, quantity: Decimal) {
+        let level = self.sides[outcome][Self::slot(side)]
+            .entry(price)
+            .or_insert(Decimal::ZERO);
+        *level += quantity;
+        if level.is_zero() {
+            self.sides[outcome][Self::slot(side)].remove(&price);
+        }
+    }
+
+    /// quantity of a price level, zero if there is no order at price
+    pub(super) fn get(&self, outcome: usize, side: Side, price: Decimal) -> Decimal {
+        self.sides[outcome][Self::slot(side)]
+            .get(&price)
+            .copied()
+            .unwrap_or(Decimal::ZERO)
+    }
+
+    /// (price, quantity) of every price level of a book side, from best to worst price
+    pub(super) fn iter(
+        &self,
+        outcome: usize,
+        side: Side,
+    ) -> Box<dyn Iterator<Item = (Decimal, Decimal)> + '_> {
+        let levels = self.sides[outcome][Self::slot(side)]
+            .iter()
+            .map(|(price, quantity)| (*price, *quantity));
+        match side {
+            Side::Buy => Box::new(levels.rev()),
+            Side::Sell => Box::new(levels),
+        }
+    }
+
+    pub(super) fn clear(&mut self) {
+        for sides in self.sides.iter_mut() {
+            sides.iter_mut().for_each(BTreeMap::clear);
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use std::hash::Hash;
+
+use rust_decimal::Decimal;
+
+use crate::{command::Command, errors, rules::TradingRules};
+
+use super::PieOrderBook;
+
+impl<OrderID, AccountID> PieOrderBook<OrderID, AccountID>
+where
+    OrderID: Copy + PartialEq + Eq + Hash,
+    AccountID: Copy + PartialEq + Eq + Hash,
+{
+    /// Set the price and quantity grid of new orders. Every resting order
+    /// must already be on the new grid.
+    pub fn set_trading_rules(
+        &mut self,
+        rules: TradingRules,
+    ) -> Result<(), errors::SetTradingRules> {
+        let non_positive = [rules.tick_size, rules.lot_size, rules.min_quantity]
+            .into_iter()
+            .flatten()
+            .any(|size| size <= Decimal::ZERO);
+        if non_positive {
+            return Err(errors::SetTradingRules::NonPositiveSize);
+        }
+        if !Self::on_grid(self.contract_price, rules.tick_size) {
+            return Err(errors::SetTradingRules::ContractPriceOffTick);
+        }
+
+        // minimum quantity only applies to new orders, resting orders may be partially filled
+        let off_grid = self.order_index.values().any(|order| {
+            !Self::on_grid(order.price, rules.tick_size)
+                || !Self::on_grid(order.quantity, rules.lot_size)
+                || !Self::on_grid(order.hidden, rules.lot_size)
+        });
+        if off_grid {
+            return Err(errors::SetTradingRules::RestingOrderOffGrid);
+        }
+
+        self.trading_rules = rules;
+        self.command_log.push(Command::SetTradingRules { rules });
+
+        Ok(())
+    }
+
+    /// Returns the price and quantity grid of new orders
+    pub fn get_trading_rules(&self) -> &TradingRules {
+        &self.trading_rules
+    }
+
+    /// checks that price, if any, and quantity of a new order are on the grid
+    pub(super) fn check_trading_rules(
+        &self,
+        price: Option<Decimal>,
+        quantity: Decimal,
+    ) -> Result<(), errors::ProcessLimitOrder> {
+        let rules = &self.trading_rules;
+
+        if price.is_some_and(|price| !Self::on_grid(price, rules.tick_size)) {
+            return Err(errors::ProcessLimitOrder::PriceOffTick);
+        }
+        if !Self::on_grid(quantity, rules.lot_size) {
+            return Err(errors::ProcessLimitOrder::QuantityOffLot);
+        }
+        if rules.min_quantity.is_some_and(|min| quantity < min) {
+            return Err(errors::ProcessLimitOrder::QuantityBelowMinimum);
+        }
+
+        Ok(())
+    }
+
+    pub(super) fn on_grid(value: Decimal, step: Option<Decimal>) -> bool {
+        step.is_none_or(|step| value.checked_rem(step).is_some_and(|rem| rem.is_zero()))
+    }
+}
@@ -0,0 +1,118 @@
+use std::hash::Hash;
+
+use rust_decimal::Decimal;
+use rust_ob::Side;
+
+use crate::{errors, order::SelfTradePrevention};
+
+use super::PieOrderBook;
+
+/// what the matching loop does after self trade prevention
+pub(super) enum SelfTradeAction {
+    /// match up to quantity_limit, no resting order of the account is involved
+    Match { quantity_limit: Decimal },
+    /// resting orders changed, look for the next match again
+    Retry,
+    /// incoming order was cancelled
+    Stop,
+}
+
+impl<OrderID, AccountID> PieOrderBook<OrderID, AccountID>
+where
+    OrderID: Copy + PartialEq + Eq + Hash,
+    AccountID: Copy + PartialEq + Eq + Hash,
+{
+    /// Applies the self trade prevention of order id before it matches the highest
+    /// priority resting orders of maker_side of books. Cancelled orders are added to cancelled.
+    pub(super) fn prevent_self_trade(
+        &mut self,
+        id: OrderID,
+        books: &[usize],
+        maker_side: Side,
+        quantity: &mut Decimal,
+        cancelled: &mut Vec<OrderID>,
+    ) -> Result<SelfTradeAction, errors::ProcessLimitOrder> {
+        let no_limit = SelfTradeAction::Match {
+            quantity_limit: Decimal::MAX,
+        };
+        let Some(taker) = self.order_accounts.get(&id) else {
+            return Ok(no_limit);
+        };
+        let (account, Some(mode)) = (taker.account, taker.self_trade_prevention) else {
+            return Ok(no_limit);
+        };
+
+        // highest priority resting order of every order book the next match touches
+        let makers: Vec<OrderID> = books
+            .iter()
+            .filter_map(|i| self.order_books[*i].get_highest_priority_order(maker_side))
+            .collect();
+        let self_makers: Vec<OrderID> = makers
+            .iter()
+            .copied()
+            .filter(|maker| {
+                self.order_accounts
+                    .get(maker)
+                    .is_some_and(|account_order| account_order.account == account)
+            })
+            .collect();
+
+        if self_makers.is_empty() {
+            // the orders behind the highest priority ones may belong to the account
+            let quantity_limit = makers
+                .iter()
+                .filter_map(|maker| self.order_index.get(maker))
+                .map(|order| order.quantity)
+                .min()
+                .unwrap_or(Decimal::MAX);
+
+            return Ok(SelfTradeAction::Match { quantity_limit });
+        }
+
+        match mode {
+            SelfTradePrevention::CancelNewest => {
+                cancelled.push(id);
+                Ok(SelfTradeAction::Stop)
+            }
+            SelfTradePrevention::CancelOldest | SelfTradePrevention::CancelBoth => {
+                for maker in self_makers {
+                    self.remove_resting_order(maker)?;
+                    cancelled.push(maker);
+                }
+
+                if let SelfTradePrevention::CancelBoth = mode {
+                    cancelled.push(id);
+                    Ok(SelfTradeAction::Stop)
+                } else {
+                    Ok(SelfTradeAction::Retry)
+                }
+            }
+            SelfTradePrevention::Decrement => {
+                let maker = self_makers[0];
+                let order = self.order_index.get(&maker).cloned().expect(
+                    "PieOrderBook::prevent_self_trade: order_books out of sync with order_index",
+                );
+                let decrement = order.quantity.min(*quantity);
+
+                if decrement == order.quantity && order.hidden.is_zero() {
+                    self.remove_resting_order(maker)?;
+                    cancelled.push(maker);
+                } else if decrement == order.quantity {
+                    self.order_books[order.outcome].cancel_order(maker).expect(
+                        "PieOrderBook::prevent_self_trade: order_index out of sync with order_books",
+                    );
+                    self.levels
+                        .add(order.outcome, order.side, order.price, -order.quantity);
+                    self.replenish_order(maker)?;
+                    self.update_order_collateral(maker)?;
+                } else {
+                    self.reduce_order(maker, order.quantity - decrement)?;
+                    self.emit_level_changes(vec![(order.outcome, order.side, order.price)]);
+                }
+                *quantity -= decrement;
+
+                Ok(SelfTradeAction::Retry)
+            }
+        }
+    }
+}
@@ -0,0 +1,182 @@
+use std::{collections::HashMap, hash::Hash};
+
+use crate::{errors, Snapshot};
+
+use super::PieOrderBook;
+
+impl<OrderID, AccountID> PieOrderBook<OrderID, AccountID>
+where
+    OrderID: Copy + PartialEq + Eq + Hash,
+    AccountID: Copy + PartialEq + Eq + Hash,
+{
+    /// Returns the complete state of the order book, without its command log
+    pub fn snapshot(&self) -> Snapshot<OrderID, AccountID> {
+        let mut orders: Vec<_> = self
+            .order_index
+            .iter()
+            .map(|(id, order)| (*id, order.clone()))
+            .collect();
+        orders.sort_by_key(|(_, order)| order.sequence);
+
+        Snapshot {
+            contract_price: self.contract_price,
+            outcomes: self.order_books.len(),
+            orders,
+            next_sequence: self.next_sequence,
+            positions: self
+                .position_ids
+                .iter()
+                .map(|id| (*id, self.positions[id].clone()))
+                .collect(),
+            accounts: self
+                .account_ids
+                .iter()
+                .map(|id| (*id, self.accounts[id].clone()))
+                .collect(),
+            order_accounts: self
+                .order_account_ids
+                .iter()
+                .map(|id| (*id, self.order_accounts[id].clone()))
+                .collect(),
+            stop_orders: self.stop_orders.iter().cloned().collect(),
+            last_trade_prices: self.last_trade_prices.clone(),
+            expired_until: self.expired_until,
+            earliest_expiry: self.earliest_expiry,
+            market_state: self.market_state,
+            halted: self.halted.clone(),
+            queued_orders: self.queued_orders.clone(),
+            resolution: self.resolution.clone(),
+            trading_rules: self.trading_rules,
+            fee_schedule: self.fee_schedule.clone(),
+            collected_fees: self.collected_fees,
+        }
+    }
+
+    /// Returns a copy of the order book to simulate operations on
+    pub(super) fn simulation(&self) -> Self {
+        Self::restore(self.snapshot()).expect("PieOrderBook::simulation: snapshot does not restore")
+    }
+
+    /// Reconstruct a `PieOrderBook` from a snapshot. Resting orders keep their time priority.
+    /// The restored order book starts with an empty command log.
+    pub fn restore(snapshot: Snapshot<OrderID, AccountID>) -> Result<Self, errors::Restore> {
+        let mut pie_ob = Self::with_accounts(snapshot.contract_price, snapshot.outcomes).map_err(
+            |e| match e {
+                errors::New::TooFewOutcomes => errors::Restore::TooFewOutcomes,
+                errors::New::NonPositiveContractPrice => errors::Restore::NonPositiveContractPrice,
+            },
+        )?;
+
+        let mut orders = snapshot.orders;
+        orders.sort_by_key(|(_, order)| order.sequence);
+        for (id, order) in orders {
+            let order_book = pie_ob
+                .order_books
+                .get_mut(order.outcome)
+                .ok_or(errors::Restore::InvalidOrder)?;
+
+            let crossed = !order_book
+                .process_limit_order(id, order.side, order.price, order.quantity)
+                .map_err(|_| errors::Restore::InvalidOrder)?
+                .is_empty();
+            pie_ob
+                .levels
+                .add(order.outcome, order.side, order.price, order.quantity);
+            if crossed || pie_ob.order_index.insert(id, order).is_some() {
+                return Err(errors::Restore::InvalidOrder);
+            }
+        }
+
+        let outcomes = snapshot.outcomes;
+        let unknown_outcome = |outcome: usize| outcome >= outcomes;
+        let invalid_outcomes = snapshot
+            .positions
+            .iter()
+            .any(|(_, position)| unknown_outcome(position.outcome))
+            || snapshot
+                .order_accounts
+                .iter()
+                .any(|(_, order)| unknown_outcome(order.outcome))
+            || snapshot
+                .stop_orders
+                .iter()
+                .any(|(_, order)| unknown_outcome(order.outcome))
+            || snapshot
+                .queued_orders
+                .iter()
+                .any(|(_, order)| unknown_outcome(order.outcome));
+        if invalid_outcomes {
+            return Err(errors::Restore::InvalidOrder);
+        }
+        let invalid_accounts = snapshot.accounts.iter().any(|(_, account)| {
+            account.positions.len() != outcomes || account.reserved.len() != outcomes
+        });
+        if invalid_accounts {
+            return Err(errors::Restore::InvalidAccount);
+        }
+        let outcomes_mismatch =
+            snapshot.last_trade_prices.len() != outcomes || snapshot.halted.len() != outcomes;
+        if outcomes_mismatch {
+            return Err(errors::Restore::OutcomesMismatch);
+        }
+
+        pie_ob.next_sequence = snapshot.next_sequence;
+        pie_ob.position_ids = snapshot.positions.iter().map(|(id, _)| *id).collect();
+        pie_ob.positions = HashMap::from_iter(snapshot.positions);
+        pie_ob.account_ids = snapshot.accounts.iter().map(|(id, _)| *id).collect();
+        pie_ob.accounts = HashMap::from_iter(snapshot.accounts);
+        pie_ob.order_account_ids = snapshot.order_accounts.iter().map(|(id, _)| *id).collect();
+        pie_ob.order_accounts = HashMap::from_iter(snapshot.order_accounts);
+        let unknown_accounts = pie_ob
+            .order_accounts
+            .values()
+            .any(|order| !pie_ob.accounts.contains_key(&order.account));
+        if unknown_accounts {
+            return Err(errors::Restore::InvalidAccount);
+        }
+        for (id, order) in snapshot.stop_orders {
+            pie_ob.stop_orders.push(id, order);
+        }
+        pie_ob.last_trade_prices = snapshot.last_trade_prices;
+        pie_ob.expired_until = snapshot.expired_until;
+        pie_ob.market_state = snapshot.market_state;
+        pie_ob.halted = snapshot.halted;
+        pie_ob.queued_orders = snapshot.queued_orders;
+        // orders of the snapshot must not expire before earliest_expiry
+        pie_ob.earliest_expiry = [snapshot.earliest_expiry, pie_ob.next_expiry()]
+            .into_iter()
+            .flatten()
+            .min();
+        pie_ob.resolution = snapshot.resolution;
+        pie_ob.trading_rules = snapshot.trading_rules;
+        pie_ob.fee_schedule = snapshot.fee_schedule;
+        pie_ob.collected_fees = snapshot.collected_fees;
+        pie_ob.command_log = Vec::new();
+
+        Ok(pie_ob)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<OrderID, AccountID> serde::Serialize for PieOrderBook<OrderID, AccountID>
+where
+    OrderID: Copy + PartialEq + Eq + Hash + serde::Serialize,
+    AccountID: Copy + PartialEq + Eq + Hash + serde::Serialize,
+{
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.snapshot().serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, OrderID, AccountID> serde::Deserialize<'de> for PieOrderBook<OrderID, AccountID>
+where
+    OrderID: Copy + PartialEq + Eq + Hash + serde::Deserialize<'de>,
+    AccountID: Copy + PartialEq + Eq + Hash + serde::Deserialize<'de>,
+{
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let snapshot = Snapshot::deserialize(deserializer)?;
+        Self::restore(snapshot)
+            .map_err(|e| serde::de::Error::custom(format!("invalid snapshot: {e:?}")))
+    }
+}
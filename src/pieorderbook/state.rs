@@ -0,0 +1,239 @@
+use std::hash::Hash;
+
+use rust_decimal::Decimal;
+use rust_ob::Side;
+
+use crate::{
+    command::Command,
+    errors,
+    event::Event,
+    order::{OrderOptions, TimeInForce},
+    state::MarketState,
+};
+
+use super::PieOrderBook;
+
+/// Limit order held until its outcome can be traded
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct QueuedOrder<AccountID> {
+    pub(super) outcome: usize,
+    #[cfg_attr(feature = "serde", serde(with = "crate::snapshot::SideDef"))]
+    pub(super) side: Side,
+    pub(super) price: Decimal,
+    pub(super) quantity: Decimal,
+    pub(super) options: OrderOptions<AccountID>,
+}
+
+/// What happens to a new order given the market state
+pub(super) enum Admission {
+    Match,
+    Queue,
+}
+
+impl<OrderID, AccountID> PieOrderBook<OrderID, AccountID>
+where
+    OrderID: Copy + PartialEq + Eq + Hash,
+    AccountID: Copy + PartialEq + Eq + Hash,
+{
+    /// Move the market to state. Opening the market processes the queued
+    /// limit orders of outcomes that are not halted, in the order they were queued.
+    ///
+    /// The market moves from `MarketState::PreOpen` to `MarketState::Open`, between
+    /// `MarketState::Open` and `MarketState::Halted`, and from any of them to
+    /// `MarketState::Closed`. A new market is open, it moves back to
+    /// `MarketState::PreOpen` until the first order rests or matches.
+    /// `MarketState::Resolved` and `MarketState::Voided` are only reached by
+    /// resolving the market.
+    pub fn set_market_state(&mut self, state: MarketState) -> Result<(), errors::SetMarketState> {
+        if self.resolution.is_some() {
+            return Err(errors::SetMarketState::MarketResolved);
+        }
+        if matches!(state, MarketState::Resolved | MarketState::Voided) {
+            return Err(errors::SetMarketState::InvalidState);
+        }
+        if !self.transition_allowed(state) {
+            return Err(errors::SetMarketState::InvalidTransition);
+        }
+
+        self.market_state = state;
+        self.emit(Event::MarketStateChanged(state));
+        self.command_log.push(Command::SetMarketState { state });
+        self.release_queued_orders();
+
+        Ok(())
+    }
+
+    fn transition_allowed(&self, state: MarketState) -> bool {
+        match (self.market_state, state) {
+            (MarketState::PreOpen, MarketState::Open)
+            | (MarketState::Open, MarketState::Halted)
+            | (MarketState::Halted, MarketState::Open) => true,
+            (
+                MarketState::PreOpen | MarketState::Open | MarketState::Halted,
+                MarketState::Closed,
+            ) => true,
+            // nothing rested or matched yet
+            (MarketState::Open, MarketState::PreOpen) => {
+                self.next_sequence == 0 && self.positions.is_empty()
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns the lifecycle state of the market
+    pub fn get_market_state(&self) -> MarketState {
+        self.market_state
+    }
+
+    /// Halt trading of outcome. Its limit orders are queued until it is resumed
+    /// and it is not matched through from the other outcome order books.
+    pub fn halt_outcome(&mut self, outcome: usize) -> Result<(), errors::HaltOutcome> {
+        self.check_halt_outcome(outcome)?;
+
+        self.halted[outcome] = true;
+        self.emit(Event::OutcomeHalted { outcome });
+        self.command_log.push(Command::HaltOutcome { outcome });
+
+        Ok(())
+    }
+
+    /// Resume trading of a halted outcome, processing its queued limit orders
+    /// if the market is open
+    pub fn resume_outcome(&mut self, outcome: usize) -> Result<(), errors::HaltOutcome> {
+        self.check_halt_outcome(outcome)?;
+
+        self.halted[outcome] = false;
+        self.emit(Event::OutcomeResumed { outcome });
+        self.command_log.push(Command::ResumeOutcome { outcome });
+        self.release_queued_orders();
+
+        Ok(())
+    }
+
+    /// Returns whether trading of outcome is halted
+    pub fn is_outcome_halted(&self, outcome: usize) -> bool {
+        self.halted.get(outcome).copied().unwrap_or(false)
+    }
+
+    fn check_halt_outcome(&self, outcome: usize) -> Result<(), errors::HaltOutcome> {
+        if self.resolution.is_some() {
+            return Err(errors::HaltOutcome::MarketResolved);
+        }
+        if outcome >= self.order_books.len() {
+            return Err(errors::HaltOutcome::InvalidOutcome);
+        }
+
+        Ok(())
+    }
+
+    /// Decides whether a new order of outcome is matched or queued
+    pub(super) fn admission(&self, outcome: usize) -> Result<Admission, errors::ProcessLimitOrder> {
+        match self.market_state {
+            MarketState::Resolved | MarketState::Voided => {
+                Err(errors::ProcessLimitOrder::MarketResolved)
+            }
+            MarketState::Closed => Err(errors::ProcessLimitOrder::MarketNotOpen),
+            MarketState::PreOpen | MarketState::Halted => Ok(Admission::Queue),
+            MarketState::Open if self.is_outcome_halted(outcome) => Ok(Admission::Queue),
+            MarketState::Open => Ok(Admission::Match),
+        }
+    }
+
+    /// Whether the complement of outcome can be matched, which needs every other outcome.
+    /// The other outcome order books are empty to the complement if any of them is halted.
+    pub(super) fn others_tradable(&self, outcome: usize) -> bool {
+        self.halted
+            .iter()
+            .enumerate()
+            .all(|(i, halted)| i == outcome || !halted)
+    }
+
+    pub(super) fn queue_order(&mut self, id: OrderID, order: QueuedOrder<AccountID>) {
+        self.emit(Event::OrderQueued {
+            order: id,
+            outcome: order.outcome,
+            side: order.side,
+            price: order.price,
+            quantity: order.quantity,
+        });
+        self.track_expiry(order.options.time_in_force.expires_at());
+        self.queued_orders.push((id, order));
+    }
+
+    pub(super) fn emit_queued_order_cancelled(
+        &mut self,
+        id: OrderID,
+        order: &QueuedOrder<AccountID>,
+    ) {
+        self.emit(Event::OrderCancelled {
+            order: id,
+            outcome: order.outcome,
+            side: order.side,
+            price: order.price,
+            quantity: order.quantity,
+        });
+    }
+
+    /// removes queued orders with a good till time at or before now
+    pub(super) fn expire_queued_orders(
+        &mut self,
+        now: u64,
+        expired: &mut Vec<OrderID>,
+    ) -> Result<(), errors::ExpireOrders> {
+        let mut expired_queued = Vec::new();
+        self.queued_orders.retain(|(id, order)| {
+            let keep = match order.options.time_in_force {
+                TimeInForce::GoodTillTime(expires_at) => expires_at > now,
+                _ => true,
+            };
+            if !keep {
+                expired_queued.push((*id, order.clone()));
+            }
+            keep
+        });
+        for (id, order) in expired_queued {
+            self.update_order_collateral(id)
+                .map_err(|_| errors::ExpireOrders::ArithmeticOverflow)?;
+            self.emit_queued_order_cancelled(id, &order);
+            expired.push(id);
+        }
+
+        Ok(())
+    }
+
+    /// Processes queued orders that can be matched now, in the order they were queued.
+    /// They are part of the operation that released them and are not logged.
+    fn release_queued_orders(&mut self) {
+        if self.market_state != MarketState::Open {
+            return;
+        }
+
+        let mut index = 0;
+        while index < self.queued_orders.len() {
+            let (_, order) = &self.queued_orders[index];
+            if self.is_outcome_halted(order.outcome) {
+                index += 1;
+                continue;
+            }
+
+            // the collateral of the queued order is checked again once it is processed
+            let (id, order) = self.queued_orders.remove(index);
+            let executed = self.update_order_collateral(id).and_then(|_| {
+                self.execute_limit_order(
+                    id,
+                    order.outcome,
+                    order.side,
+                    order.price,
+                    order.quantity,
+                    order.options,
+                )
+            });
+            if executed.is_err() {
+                self.emit(Event::QueuedOrderRejected { order: id });
+            }
+        }
+
+        self.trigger_stop_orders();
+    }
+}
@@ -0,0 +1,319 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    hash::Hash,
+};
+
+use rust_decimal::Decimal;
+use rust_ob::Side;
+
+use crate::{
+    command::Command,
+    errors,
+    event::Event,
+    order::{StopOrder, StopTrigger},
+    state::MarketState,
+};
+
+use super::PieOrderBook;
+
+/// prices stop orders of an outcome trigger at: last trade, best ask and best bid
+type TriggerPrices = [Option<Decimal>; 3];
+
+/// Stop orders held off-book, indexed by outcome, trigger, side and trigger price
+#[derive(Debug)]
+pub(super) struct StopBook<OrderID, AccountID> {
+    // stop orders by the sequence they were submitted in
+    orders: BTreeMap<u64, (OrderID, StopOrder<AccountID>)>,
+    next_sequence: u64,
+    // (trigger price, sequence) of the stop orders of every outcome, by trigger and side
+    index: Vec<[BTreeSet<(Decimal, u64)>; 4]>,
+    // prices every outcome was last checked at without triggering a stop order
+    checked: Vec<Option<TriggerPrices>>,
+}
+
+impl<OrderID, AccountID> StopBook<OrderID, AccountID>
+where
+    OrderID: Copy + PartialEq,
+{
+    pub(super) fn new(outcomes: usize) -> Self {
+        StopBook {
+            orders: BTreeMap::new(),
+            next_sequence: 0,
+            index: (0..outcomes).map(|_| Default::default()).collect(),
+            checked: vec![None; outcomes],
+        }
+    }
+
+    fn slot(trigger: StopTrigger, side: Side) -> usize {
+        let trigger = match trigger {
+            StopTrigger::LastTrade => 0,
+            StopTrigger::Quote => 2,
+        };
+        match side {
+            Side::Buy => trigger,
+            Side::Sell => trigger + 1,
+        }
+    }
+
+    pub(super) fn push(&mut self, id: OrderID, order: StopOrder<AccountID>) {
+        let sequence = self.next_sequence;
+        self.next_sequence += 1;
+
+        let slot = Self::slot(order.trigger, order.side);
+        self.index[order.outcome][slot].insert((order.trigger_price, sequence));
+        // the new stop order has not been checked yet
+        self.checked[order.outcome] = None;
+        self.orders.insert(sequence, (id, order));
+    }
+
+    fn remove_sequence(&mut self, sequence: u64) -> Option<(OrderID, StopOrder<AccountID>)> {
+        let (id, order) = self.orders.remove(&sequence)?;
+        let slot = Self::slot(order.trigger, order.side);
+        self.index[order.outcome][slot].remove(&(order.trigger_price, sequence));
+
+        Some((id, order))
+    }
+
+    pub(super) fn remove(&mut self, id: OrderID) -> Option<(OrderID, StopOrder<AccountID>)> {
+        let sequence = self
+            .orders
+            .iter()
+            .find(|(_, (stop_id, _))| *stop_id == id)
+            .map(|(sequence, _)| *sequence)?;
+
+        self.remove_sequence(sequence)
+    }
+
+    /// Removes the stop orders keep returns false for, in the order they were submitted
+    pub(super) fn remove_unless(
+        &mut self,
+        mut keep: impl FnMut(&StopOrder<AccountID>) -> bool,
+    ) -> Vec<OrderID> {
+        let sequences: Vec<u64> = self
+            .orders
+            .iter()
+            .filter(|(_, (_, order))| !keep(order))
+            .map(|(sequence, _)| *sequence)
+            .collect();
+
+        sequences
+            .into_iter()
+            .filter_map(|sequence| self.remove_sequence(sequence))
+            .map(|(id, _)| id)
+            .collect()
+    }
+
+    /// Removes every stop order, in the order they were submitted
+    pub(super) fn take(&mut self) -> Vec<(OrderID, StopOrder<AccountID>)> {
+        let outcomes = self.index.len();
+        std::mem::replace(self, Self::new(outcomes))
+            .orders
+            .into_values()
+            .collect()
+    }
+
+    /// Returns the stop orders in the order they were submitted
+    pub(super) fn iter(&self) -> impl Iterator<Item = &(OrderID, StopOrder<AccountID>)> {
+        self.orders.values()
+    }
+
+    pub(super) fn has_outcome(&self, outcome: usize) -> bool {
+        self.index[outcome].iter().any(|set| !set.is_empty())
+    }
+
+    /// Returns the sequence of the first submitted stop order of outcome triggered
+    /// at prices, if any. Outcomes are only checked again once their prices change.
+    fn triggered(&mut self, outcome: usize, prices: TriggerPrices) -> Option<u64> {
+        if self.checked[outcome] == Some(prices) {
+            return None;
+        }
+
+        let [last_trade, best_ask, best_bid] = prices;
+        let triggered = [
+            (StopTrigger::LastTrade, Side::Buy, last_trade),
+            (StopTrigger::LastTrade, Side::Sell, last_trade),
+            (StopTrigger::Quote, Side::Buy, best_ask),
+            (StopTrigger::Quote, Side::Sell, best_bid),
+        ]
+        .into_iter()
+        .filter_map(|(trigger, side, price)| {
+            let price = price?;
+            let stops = &self.index[outcome][Self::slot(trigger, side)];
+            // buy stops trigger at or above their trigger price, sell stops at or below it
+            match side {
+                Side::Buy => stops.range(..=(price, u64::MAX)).map(|(_, s)| *s).min(),
+                Side::Sell => stops.range((price, 0)..).map(|(_, s)| *s).min(),
+            }
+        })
+        .min();
+
+        if triggered.is_none() {
+            self.checked[outcome] = Some(prices);
+        }
+        triggered
+    }
+}
+
+impl<OrderID, AccountID> PieOrderBook<OrderID, AccountID>
+where
+    OrderID: Copy + PartialEq + Eq + Hash,
+    AccountID: Copy + PartialEq + Eq + Hash,
+{
+    /// Submit a stop order, held off-book until it triggers.
+    ///
+    /// A triggered stop order is submitted as a limit order at its limit price,
+    /// or as a market order without one, under its own id. Collateral is only
+    /// checked once it triggers, a rejected stop order is dropped.
+    /// A stop order whose trigger price is already reached triggers immediately.
+    /// Stop orders only trigger while the market is open and their outcome is not halted.
+    /// Good till time stop orders must expire after the current time of the order book clock.
+    pub fn submit_stop_order(
+        &mut self,
+        id: OrderID,
+        order: StopOrder<AccountID>,
+    ) -> Result<(), errors::SubmitStopOrder> {
+        self.submit_stop_order_at(self.now(), id, order)
+    }
+
+    pub(super) fn submit_stop_order_at(
+        &mut self,
+        now: u64,
+        id: OrderID,
+        order: StopOrder<AccountID>,
+    ) -> Result<(), errors::SubmitStopOrder> {
+        self.expire_stale_orders(now)?;
+        self.validate_order(id, order.outcome, order.quantity)?;
+        if self.market_state == MarketState::Closed {
+            return Err(errors::SubmitStopOrder::MarketNotOpen);
+        }
+
+        let in_range = |price: Decimal| price > Decimal::ZERO && price < self.contract_price;
+        let failed_validation = !in_range(order.trigger_price)
+            || order.limit_price.is_some_and(|price| !in_range(price));
+        if failed_validation {
+            return Err(errors::SubmitStopOrder::OrderValidationFailed);
+        }
+        self.check_trading_rules(Some(order.trigger_price), order.quantity)?;
+        self.check_trading_rules(order.limit_price, order.quantity)?;
+        let time_in_force = order.options.time_in_force;
+        if self.expired(time_in_force) || Self::expires_by(time_in_force, now) {
+            return Err(errors::SubmitStopOrder::OrderValidationFailed);
+        }
+
+        self.track_expiry(time_in_force.expires_at());
+        self.stop_orders.push(id, order.clone());
+        self.command_log
+            .push(Command::SubmitStopOrder { id, order, now });
+        self.trigger_stop_orders();
+
+        Ok(())
+    }
+
+    /// Cancel a stop order that has not triggered yet
+    pub fn cancel_stop_order(&mut self, id: OrderID) -> Result<(), errors::CancelOrder> {
+        self.stop_orders
+            .remove(id)
+            .ok_or(errors::CancelOrder::UnknownOrder)?;
+
+        self.emit(Event::StopCancelled { order: id });
+        self.command_log.push(Command::CancelStopOrder { id });
+
+        Ok(())
+    }
+
+    /// Returns a stop order that has not triggered yet
+    pub fn get_stop_order(&self, id: OrderID) -> Option<&StopOrder<AccountID>> {
+        self.stop_orders
+            .iter()
+            .find(|(stop_id, _)| *stop_id == id)
+            .map(|(_, order)| order)
+    }
+
+    /// Returns the last price outcome traded at, directly or through the other outcome order books
+    pub fn get_last_trade_price(&self, outcome: usize) -> Option<Decimal> {
+        self.last_trade_prices.get(outcome).copied().flatten()
+    }
+
+    /// Submits triggered stop orders, in the order they were submitted, until none triggers.
+    /// Triggered orders are part of the operation that triggered them and are not logged.
+    pub(super) fn trigger_stop_orders(&mut self) {
+        while let Some((id, order)) = self.next_triggered_stop_order() {
+            self.emit(Event::StopTriggered { order: id });
+
+            let rejected = match order.limit_price {
+                Some(price) => self
+                    .execute_limit_order(
+                        id,
+                        order.outcome,
+                        order.side,
+                        price,
+                        order.quantity,
+                        order.options,
+                    )
+                    .is_err(),
+                None => self
+                    .execute_market_order(
+                        id,
+                        order.outcome,
+                        order.side,
+                        order.quantity,
+                        None,
+                        order.options,
+                    )
+                    .is_err(),
+            };
+            if rejected {
+                self.emit(Event::StopRejected { order: id });
+            }
+        }
+    }
+
+    /// removes the first submitted stop order that is triggered, if any
+    fn next_triggered_stop_order(&mut self) -> Option<(OrderID, StopOrder<AccountID>)> {
+        // triggered orders are never queued
+        if self.market_state != MarketState::Open {
+            return None;
+        }
+
+        let mut next: Option<u64> = None;
+        for outcome in 0..self.order_books.len() {
+            if self.is_outcome_halted(outcome) || !self.stop_orders.has_outcome(outcome) {
+                continue;
+            }
+            let prices = self.trigger_prices(outcome);
+            if let Some(sequence) = self.stop_orders.triggered(outcome, prices) {
+                next = Some(next.map_or(sequence, |next| next.min(sequence)));
+            }
+        }
+
+        self.stop_orders.remove_sequence(next?)
+    }
+
+    /// last trade price, best ask and best bid of outcome, including the
+    /// liquidity implied by the other outcome order books
+    fn trigger_prices(&self, outcome: usize) -> TriggerPrices {
+        let best = |side: Side| {
+            let direct = self.order_books[outcome]
+                .get_highest_priority_price_quantity(side)
+                .map(|(price, _)| price);
+            let implied = self
+                .get_other_order_books_best_price_quantity(outcome, side)
+                .ok()
+                .filter(|(price, quantity)| {
+                    !quantity.is_zero() && *price > Decimal::ZERO && *price < self.contract_price
+                })
+                .map(|(price, _)| price);
+
+            match side {
+                Side::Buy => direct.max(implied),
+                Side::Sell => direct.into_iter().chain(implied).min(),
+            }
+        };
+
+        [
+            self.get_last_trade_price(outcome),
+            best(Side::Sell),
+            best(Side::Buy),
+        ]
+    }
+}
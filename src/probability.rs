@@ -0,0 +1,22 @@
+use rust_decimal::Decimal;
+
+/// Market implied probabilities of every outcome
+#[derive(Debug, PartialEq, Clone)]
+pub struct Probabilities {
+    pub outcomes: Vec<OutcomeProbability>,
+    /// sum of the raw probabilities minus one. Positive values are an overround,
+    /// negative values an underround.
+    pub overround: Decimal,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct OutcomeProbability {
+    pub outcome: usize,
+    /// mid price between the best effective bid and ask. A missing bid counts as zero
+    /// and a missing ask as the contract price.
+    pub mid: Decimal,
+    /// mid price relative to the contract price
+    pub raw: Decimal,
+    /// raw probability scaled so that all outcomes sum to one
+    pub normalized: Decimal,
+}
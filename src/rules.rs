@@ -0,0 +1,15 @@
+use rust_decimal::Decimal;
+
+/// Price and quantity grid orders of a `PieOrderBook` must be on
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TradingRules {
+    /// prices must be a multiple of tick_size. The contract price must be a
+    /// multiple as well, so that prices implied by the other outcome order books
+    /// are always on the grid.
+    pub tick_size: Option<Decimal>,
+    /// quantities must be a multiple of lot_size
+    pub lot_size: Option<Decimal>,
+    /// quantities must be at least min_quantity
+    pub min_quantity: Option<Decimal>,
+}
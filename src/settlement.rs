@@ -0,0 +1,45 @@
+use rust_decimal::Decimal;
+
+/// How a `PieOrderBook` market was resolved
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Resolution {
+    /// the outcome pays out the full contract price
+    Outcome(usize),
+    /// each outcome pays out the given amount per contract
+    Partial(Vec<Decimal>),
+    /// market was cancelled and every position is refunded at cost
+    Void,
+}
+
+/// Result of resolving a `PieOrderBook` market
+#[derive(Debug, PartialEq, Clone)]
+pub struct Settlement<OrderID, AccountID = ()> {
+    pub resolution: Resolution,
+    /// resting orders that were cancelled by the resolution, in time priority,
+    /// followed by the stop orders and queued orders
+    pub cancelled: Vec<OrderID>,
+    /// in the order the positions were first matched
+    pub payouts: Vec<Payout<OrderID>>,
+    /// sum of payouts of the orders placed on behalf of each account and of
+    /// its complete sets, in the order the accounts were created.
+    /// These amounts have already been credited to the accounts.
+    pub account_payouts: Vec<AccountPayout<AccountID>>,
+}
+
+/// Amount owed to the holder of an order's position.
+/// Negative amounts are owed by the holder (short positions).
+#[derive(Debug, PartialEq, Clone)]
+pub struct Payout<OrderID> {
+    pub order: OrderID,
+    pub outcome: usize,
+    pub quantity: Decimal,
+    pub amount: Decimal,
+}
+
+/// Amount credited to an account on resolution
+#[derive(Debug, PartialEq, Clone)]
+pub struct AccountPayout<AccountID> {
+    pub account: AccountID,
+    pub amount: Decimal,
+}
@@ -0,0 +1,46 @@
+use rust_decimal::Decimal;
+
+use crate::{
+    account::Account,
+    fee::FeeSchedule,
+    order::{Order, Position, StopOrder},
+    pieorderbook::{AccountOrder, QueuedOrder},
+    rules::TradingRules,
+    settlement::Resolution,
+    state::MarketState,
+};
+
+/// Complete state of a `PieOrderBook`, except for its event sink, clock and command log.
+/// With the `serde` feature enabled it can be serialized to checkpoint
+/// an order book instead of replaying its command log.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Snapshot<OrderID, AccountID = ()> {
+    pub(crate) contract_price: Decimal,
+    pub(crate) outcomes: usize,
+    /// resting orders in time priority
+    pub(crate) orders: Vec<(OrderID, Order)>,
+    pub(crate) next_sequence: u64,
+    pub(crate) positions: Vec<(OrderID, Position)>,
+    pub(crate) accounts: Vec<(AccountID, Account)>,
+    pub(crate) order_accounts: Vec<(OrderID, AccountOrder<AccountID>)>,
+    pub(crate) stop_orders: Vec<(OrderID, StopOrder<AccountID>)>,
+    pub(crate) last_trade_prices: Vec<Option<Decimal>>,
+    pub(crate) expired_until: Option<u64>,
+    pub(crate) earliest_expiry: Option<u64>,
+    pub(crate) market_state: MarketState,
+    pub(crate) halted: Vec<bool>,
+    pub(crate) queued_orders: Vec<(OrderID, QueuedOrder<AccountID>)>,
+    pub(crate) resolution: Option<Resolution>,
+    pub(crate) trading_rules: TradingRules,
+    pub(crate) fee_schedule: Option<FeeSchedule>,
+    pub(crate) collected_fees: Decimal,
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(remote = "rust_ob::Side")]
+pub(crate) enum SideDef {
+    Buy,
+    Sell,
+}
@@ -0,0 +1,18 @@
+/// Lifecycle of a `PieOrderBook` market
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MarketState {
+    /// limit orders are queued until the market opens, market orders are rejected
+    PreOpen,
+    /// orders are matched, except for orders of halted outcomes
+    #[default]
+    Open,
+    /// like `PreOpen`, after the market was open
+    Halted,
+    /// new orders are rejected, resting orders can still be cancelled
+    Closed,
+    /// resolved with `resolve` or `resolve_partial`
+    Resolved,
+    /// resolved with `void`
+    Voided,
+}
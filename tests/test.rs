@@ -1,10 +1,23 @@
 use rust_decimal_macros::dec;
 use rust_ob::{OrderMatch, Side};
-use rust_pie_ob::PieOrderBook;
+use rust_pie_ob::{
+    Account, AccountPayout, Command, CommandOutput, Depth, Event, Execution, FeeCharge, FeeRate,
+    FeeSchedule, FeeTier, Fill, Liquidity, ManualClock, MarketState, MatchType, OrderOptions,
+    Payout, PieOrderBook, PostOnly, PriceLevel, Quote, Resolution, SelfTradePrevention, StopOrder,
+    StopTrigger, TimeInForce, TradingRules,
+};
+
+/// options of an order placed on behalf of an account
+fn account<AccountID>(a: AccountID) -> OrderOptions<AccountID> {
+    OrderOptions {
+        account: Some(a),
+        ..Default::default()
+    }
+}
 
 #[test]
 fn process_limit_order1() {
-    let mut pie_ob = PieOrderBook::new(dec!(10), 4);
+    let mut pie_ob = PieOrderBook::new(dec!(10), 4).unwrap();
 
     use rust_pie_ob::errors::ProcessLimitOrder as E;
     assert_eq!(
@@ -43,7 +56,7 @@ fn process_limit_order1() {
 
 #[test]
 fn process_limit_order2() {
-    let mut pie_ob = PieOrderBook::new(dec!(10), 2);
+    let mut pie_ob = PieOrderBook::new(dec!(10), 2).unwrap();
 
     let res = pie_ob
         .process_limit_order(1, 0, Side::Buy, dec!(3), dec!(5))
@@ -58,7 +71,7 @@ fn process_limit_order2() {
     let mut res = pie_ob
         .process_limit_order(3, 1, Side::Buy, dec!(8), dec!(3))
         .unwrap();
-    res.sort_by(|v1, v2| v1.order.cmp(&v2.order));
+    res.sort_by_key(|v| v.order);
     assert_eq!(
         res,
         vec![
@@ -83,7 +96,7 @@ fn process_limit_order2() {
 
 #[test]
 fn process_limit_order3() {
-    let mut pie_ob = PieOrderBook::new(dec!(10), 3);
+    let mut pie_ob = PieOrderBook::new(dec!(10), 3).unwrap();
 
     let res = pie_ob
         .process_limit_order(1, 0, Side::Buy, dec!(3), dec!(5))
@@ -98,12 +111,12 @@ fn process_limit_order3() {
     let mut res = pie_ob
         .process_limit_order(3, 1, Side::Buy, dec!(8), dec!(3))
         .unwrap();
-    res.sort_by(|v1, v2| v1.order.cmp(&v2.order));
+    res.sort_by_key(|v| v.order);
 
     let mut res = pie_ob
         .process_limit_order(4, 2, Side::Buy, dec!(3), dec!(4))
         .unwrap();
-    res.sort_by(|v1, v2| v1.order.cmp(&v2.order));
+    res.sort_by_key(|v| v.order);
     assert_eq!(
         res,
         vec![
@@ -133,7 +146,22 @@ fn process_limit_order3() {
 
 #[test]
 fn cancel_order1() {
-    let mut pie_ob = PieOrderBook::new(dec!(10), 2);
+    let mut pie_ob = PieOrderBook::new(dec!(10), 2).unwrap();
+
+    assert_eq!(
+        pie_ob
+            .process_limit_order(1, 1, Side::Buy, dec!(5), dec!(1))
+            .unwrap()
+            .len(),
+        0
+    );
+
+    pie_ob.cancel_order(1).unwrap();
+}
+
+#[test]
+fn cancel_order2() {
+    let mut pie_ob = PieOrderBook::new(dec!(10), 2).unwrap();
 
     assert_eq!(
         pie_ob
@@ -143,12 +171,106 @@ fn cancel_order1() {
         0
     );
 
-    pie_ob.cancel_order(1, 1);
+    use rust_pie_ob::errors::CancelOrder as E;
+    assert_eq!(pie_ob.cancel_order(2), Err(E::UnknownOrder));
+
+    pie_ob.cancel_order(1).unwrap();
+    assert_eq!(pie_ob.cancel_order(1), Err(E::UnknownOrder));
+    assert!(pie_ob.get_order(1).is_none());
+}
+
+#[test]
+fn get_order1() {
+    let mut pie_ob = PieOrderBook::new(dec!(10), 3).unwrap();
+
+    assert_eq!(
+        pie_ob
+            .process_limit_order(1, 0, Side::Buy, dec!(3), dec!(5))
+            .unwrap()
+            .len(),
+        0
+    );
+    assert_eq!(
+        pie_ob
+            .process_limit_order(2, 1, Side::Buy, dec!(4), dec!(2))
+            .unwrap()
+            .len(),
+        0
+    );
+
+    let order = pie_ob.get_order(1).unwrap();
+    assert_eq!(order.outcome, 0);
+    assert_eq!(order.price, dec!(3));
+    assert_eq!(order.quantity, dec!(5));
+
+    // order 3 mints with orders 1 and 2, fully filling order 2
+    assert_eq!(
+        pie_ob
+            .process_limit_order(3, 2, Side::Buy, dec!(5), dec!(3))
+            .unwrap()
+            .len(),
+        3
+    );
+
+    assert_eq!(pie_ob.get_order(1).unwrap().quantity, dec!(3));
+    assert!(pie_ob.get_order(2).is_none());
+    assert_eq!(pie_ob.get_order(3).unwrap().outcome, 2);
+    assert_eq!(pie_ob.get_order(3).unwrap().quantity, dec!(1));
+
+    // cancel without knowing the outcome
+    pie_ob.cancel_order(3).unwrap();
+    pie_ob.cancel_order(1).unwrap();
+    assert!(pie_ob.get_order(1).is_none());
+    assert!(pie_ob.get_order(3).is_none());
+}
+
+#[test]
+fn new1() {
+    use rust_pie_ob::errors::New as E;
+    assert_eq!(
+        PieOrderBook::<u32>::new(dec!(10), 1).unwrap_err(),
+        E::TooFewOutcomes
+    );
+    assert_eq!(
+        PieOrderBook::<u32>::new(dec!(10), 0).unwrap_err(),
+        E::TooFewOutcomes
+    );
+    assert_eq!(
+        PieOrderBook::<u32>::new(dec!(0), 2).unwrap_err(),
+        E::NonPositiveContractPrice
+    );
+    assert_eq!(
+        PieOrderBook::<u32>::new(dec!(-10), 2).unwrap_err(),
+        E::NonPositiveContractPrice
+    );
+}
+
+#[test]
+fn duplicate_order_id1() {
+    let mut pie_ob = PieOrderBook::new(dec!(10), 2).unwrap();
+
+    assert_eq!(
+        pie_ob
+            .process_limit_order(1, 0, Side::Buy, dec!(5), dec!(1))
+            .unwrap()
+            .len(),
+        0
+    );
+
+    use rust_pie_ob::errors::ProcessLimitOrder as E;
+    assert_eq!(
+        pie_ob.process_limit_order(1, 0, Side::Buy, dec!(4), dec!(1)),
+        Err(E::DuplicateOrderId)
+    );
+    assert_eq!(
+        pie_ob.process_limit_order(1, 1, Side::Sell, dec!(4), dec!(1)),
+        Err(E::DuplicateOrderId)
+    );
 }
 
 #[test]
 fn general1() {
-    let mut pie_ob = PieOrderBook::new(dec!(100), 4);
+    let mut pie_ob = PieOrderBook::new(dec!(100), 4).unwrap();
 
     assert_eq!(
         pie_ob
@@ -189,7 +311,7 @@ fn general1() {
     let mut res = pie_ob
         .process_limit_order(6, 3, Side::Buy, dec!(1), dec!(8))
         .unwrap();
-    res.sort_by(|v1, v2| v1.order.cmp(&v2.order));
+    res.sort_by_key(|v| v.order);
     assert_eq!(
         res,
         vec![
@@ -226,13 +348,13 @@ fn general1() {
         ]
     );
 
-    pie_ob.cancel_order(0, 3);
-    pie_ob.cancel_order(3, 6);
+    pie_ob.cancel_order(3).unwrap();
+    pie_ob.cancel_order(6).unwrap();
 }
 
 #[test]
 fn general2() {
-    let mut pie_ob = PieOrderBook::new(dec!(100), 3);
+    let mut pie_ob = PieOrderBook::new(dec!(100), 3).unwrap();
 
     assert_eq!(
         pie_ob
@@ -385,7 +507,7 @@ fn general2() {
     let mut res = pie_ob
         .process_limit_order(22, 2, Side::Buy, dec!(64), dec!(17))
         .unwrap();
-    res.sort_by(|v1, v2| v1.order.cmp(&v2.order));
+    res.sort_by_key(|v| v.order);
     assert_eq!(
         res,
         vec![
@@ -428,7 +550,7 @@ fn general2() {
     let mut res = pie_ob
         .process_limit_order(24, 2, Side::Sell, dec!(50), dec!(3))
         .unwrap();
-    res.sort_by(|v1, v2| v1.order.cmp(&v2.order));
+    res.sort_by_key(|v| v.order);
     assert_eq!(
         res,
         vec![
@@ -458,7 +580,7 @@ fn general2() {
     let mut res = pie_ob
         .process_limit_order(25, 0, Side::Sell, dec!(5), dec!(44))
         .unwrap();
-    res.sort_by(|v1, v2| v1.order.cmp(&v2.order));
+    res.sort_by_key(|v| v.order);
     assert_eq!(
         res,
         vec![
@@ -501,7 +623,7 @@ fn general2() {
     let mut res = pie_ob
         .process_limit_order(27, 0, Side::Buy, dec!(13), dec!(36))
         .unwrap();
-    res.sort_by(|v1, v2| v1.order.cmp(&v2.order));
+    res.sort_by_key(|v| v.order);
     assert_eq!(
         res,
         vec![
@@ -546,7 +668,7 @@ fn general2() {
     let mut res = pie_ob
         .process_limit_order(28, 2, Side::Sell, dec!(57), dec!(25))
         .unwrap();
-    res.sort_by(|v1, v2| v1.order.cmp(&v2.order));
+    res.sort_by_key(|v| v.order);
     assert_eq!(
         res,
         vec![
@@ -568,3 +690,2482 @@ fn general2() {
         ]
     );
 }
+
+#[test]
+fn resolve1() {
+    let mut pie_ob = PieOrderBook::new(dec!(10), 3).unwrap();
+
+    assert_eq!(
+        pie_ob
+            .process_limit_order(1, 0, Side::Buy, dec!(3), dec!(5))
+            .unwrap()
+            .len(),
+        0
+    );
+    assert_eq!(
+        pie_ob
+            .process_limit_order(2, 1, Side::Buy, dec!(4), dec!(2))
+            .unwrap()
+            .len(),
+        0
+    );
+    assert_eq!(
+        pie_ob
+            .process_limit_order(3, 2, Side::Buy, dec!(5), dec!(3))
+            .unwrap()
+            .len(),
+        3
+    );
+    assert_eq!(
+        pie_ob
+            .process_limit_order(4, 0, Side::Sell, dec!(2), dec!(1))
+            .unwrap()
+            .len(),
+        2
+    );
+
+    let mut settlement = pie_ob.resolve(0).unwrap();
+    settlement.cancelled.sort();
+    settlement.payouts.sort_by_key(|v| v.order);
+
+    assert_eq!(settlement.resolution, Resolution::Outcome(0));
+    assert_eq!(settlement.cancelled, vec![1, 3]);
+    assert_eq!(
+        settlement.payouts,
+        vec![
+            Payout {
+                order: 1,
+                outcome: 0,
+                quantity: dec!(3),
+                amount: dec!(30)
+            },
+            Payout {
+                order: 2,
+                outcome: 1,
+                quantity: dec!(2),
+                amount: dec!(0)
+            },
+            Payout {
+                order: 3,
+                outcome: 2,
+                quantity: dec!(2),
+                amount: dec!(0)
+            },
+            Payout {
+                order: 4,
+                outcome: 0,
+                quantity: dec!(-1),
+                amount: dec!(-10)
+            },
+        ]
+    );
+
+    // market is frozen
+    assert!(pie_ob.get_order(1).is_none());
+    assert_eq!(pie_ob.get_resolution(), Some(&Resolution::Outcome(0)));
+    assert_eq!(
+        pie_ob.process_limit_order(5, 0, Side::Buy, dec!(3), dec!(5)),
+        Err(rust_pie_ob::errors::ProcessLimitOrder::MarketResolved)
+    );
+    assert_eq!(
+        pie_ob.resolve(1),
+        Err(rust_pie_ob::errors::Resolve::MarketResolved)
+    );
+}
+
+#[test]
+fn resolve2() {
+    use rust_pie_ob::errors::Resolve as E;
+
+    let mut pie_ob = PieOrderBook::new(dec!(10), 2).unwrap();
+
+    assert_eq!(
+        pie_ob
+            .process_limit_order(1, 0, Side::Buy, dec!(6), dec!(2))
+            .unwrap()
+            .len(),
+        0
+    );
+    assert_eq!(
+        pie_ob
+            .process_limit_order(2, 1, Side::Buy, dec!(4), dec!(2))
+            .unwrap()
+            .len(),
+        2
+    );
+
+    assert_eq!(pie_ob.resolve(2), Err(E::InvalidOutcome));
+    assert_eq!(
+        pie_ob.resolve_partial(vec![dec!(5), dec!(6)]),
+        Err(E::InvalidPayouts)
+    );
+    assert_eq!(
+        pie_ob.resolve_partial(vec![dec!(11), dec!(-1)]),
+        Err(E::InvalidPayouts)
+    );
+    assert_eq!(
+        pie_ob.resolve_partial(vec![dec!(10)]),
+        Err(E::InvalidPayouts)
+    );
+
+    let mut settlement = pie_ob.resolve_partial(vec![dec!(7), dec!(3)]).unwrap();
+    settlement.payouts.sort_by_key(|v| v.order);
+    assert_eq!(
+        settlement
+            .payouts
+            .iter()
+            .map(|v| v.amount)
+            .collect::<Vec<_>>(),
+        vec![dec!(14), dec!(6)]
+    );
+}
+
+#[test]
+fn void1() {
+    let mut pie_ob = PieOrderBook::new(dec!(10), 2).unwrap();
+
+    assert_eq!(
+        pie_ob
+            .process_limit_order(1, 0, Side::Buy, dec!(6), dec!(2))
+            .unwrap()
+            .len(),
+        0
+    );
+    assert_eq!(
+        pie_ob
+            .process_limit_order(2, 0, Side::Sell, dec!(5), dec!(1))
+            .unwrap()
+            .len(),
+        2
+    );
+
+    let mut settlement = pie_ob.void().unwrap();
+    settlement.payouts.sort_by_key(|v| v.order);
+
+    assert_eq!(settlement.resolution, Resolution::Void);
+    assert_eq!(settlement.cancelled, vec![1]);
+    assert_eq!(
+        settlement
+            .payouts
+            .iter()
+            .map(|v| v.amount)
+            .collect::<Vec<_>>(),
+        vec![dec!(6), dec!(-6)]
+    );
+}
+
+#[test]
+fn accounts1() {
+    let mut pie_ob = PieOrderBook::with_accounts(dec!(10), 2).unwrap();
+
+    use rust_pie_ob::errors::ProcessLimitOrder as E;
+    assert_eq!(
+        pie_ob.process_limit_order_with_options(1, 0, Side::Buy, dec!(6), dec!(2), account('a')),
+        Err(E::UnknownAccount)
+    );
+
+    pie_ob.deposit('a', dec!(100)).unwrap();
+    pie_ob.deposit('b', dec!(100)).unwrap();
+
+    assert_eq!(
+        pie_ob
+            .process_limit_order_with_options(1, 0, Side::Buy, dec!(6), dec!(2), account('a'))
+            .unwrap()
+            .matches
+            .len(),
+        0
+    );
+    assert_eq!(
+        pie_ob.get_account('a').unwrap(),
+        &Account {
+            cash: dec!(100),
+            locked: dec!(12),
+            positions: vec![dec!(0), dec!(0)],
+            reserved: vec![dec!(0), dec!(0)],
+            volume: dec!(0),
+            sets: dec!(0)
+        }
+    );
+
+    // b mints with a
+    assert_eq!(
+        pie_ob
+            .process_limit_order_with_options(2, 1, Side::Buy, dec!(5), dec!(1), account('b'))
+            .unwrap()
+            .matches
+            .len(),
+        2
+    );
+    assert_eq!(
+        pie_ob.get_account('a').unwrap(),
+        &Account {
+            cash: dec!(94),
+            locked: dec!(6),
+            positions: vec![dec!(1), dec!(0)],
+            reserved: vec![dec!(0), dec!(0)],
+            volume: dec!(1),
+            sets: dec!(0)
+        }
+    );
+    assert_eq!(
+        pie_ob.get_account('b').unwrap(),
+        &Account {
+            cash: dec!(96),
+            locked: dec!(0),
+            positions: vec![dec!(0), dec!(1)],
+            reserved: vec![dec!(0), dec!(0)],
+            volume: dec!(1),
+            sets: dec!(0)
+        }
+    );
+
+    // b sells short into a's remaining bid
+    assert_eq!(
+        pie_ob
+            .process_limit_order_with_options(3, 0, Side::Sell, dec!(6), dec!(1), account('b'))
+            .unwrap()
+            .matches
+            .len(),
+        2
+    );
+    assert_eq!(
+        pie_ob.get_account('b').unwrap(),
+        &Account {
+            cash: dec!(102),
+            locked: dec!(10),
+            positions: vec![dec!(-1), dec!(1)],
+            reserved: vec![dec!(0), dec!(0)],
+            volume: dec!(2),
+            sets: dec!(0)
+        }
+    );
+
+    use rust_pie_ob::errors::Withdraw as W;
+    assert_eq!(pie_ob.withdraw('b', dec!(93)), Err(W::InsufficientFunds));
+    assert_eq!(pie_ob.withdraw('c', dec!(1)), Err(W::UnknownAccount));
+    pie_ob.withdraw('b', dec!(92)).unwrap();
+
+    // winning outcome 0 is paid to a and taken from b
+    let mut settlement = pie_ob.resolve(0).unwrap();
+    settlement.account_payouts.sort_by_key(|v| v.account);
+    assert_eq!(
+        settlement.account_payouts,
+        vec![
+            AccountPayout {
+                account: 'a',
+                amount: dec!(20)
+            },
+            AccountPayout {
+                account: 'b',
+                amount: dec!(-10)
+            },
+        ]
+    );
+    assert_eq!(
+        pie_ob.get_account('a').unwrap(),
+        &Account {
+            cash: dec!(108),
+            locked: dec!(0),
+            positions: vec![dec!(0), dec!(0)],
+            reserved: vec![dec!(0), dec!(0)],
+            volume: dec!(2),
+            sets: dec!(0)
+        }
+    );
+    assert_eq!(pie_ob.get_account('b').unwrap().cash, dec!(0));
+}
+
+#[test]
+fn collateral1() {
+    use rust_pie_ob::errors::ProcessLimitOrder as E;
+
+    let mut pie_ob = PieOrderBook::with_accounts(dec!(10), 3).unwrap();
+    pie_ob.deposit(1, dec!(20)).unwrap();
+    pie_ob.deposit(2, dec!(20)).unwrap();
+
+    // buys risk price * quantity
+    assert_eq!(
+        pie_ob.process_limit_order_with_options(1, 0, Side::Buy, dec!(5), dec!(5), account(1)),
+        Err(E::InsufficientCollateral)
+    );
+    assert_eq!(
+        pie_ob
+            .process_limit_order_with_options(1, 0, Side::Buy, dec!(5), dec!(4), account(1))
+            .unwrap()
+            .matches
+            .len(),
+        0
+    );
+    assert_eq!(pie_ob.get_account(1).unwrap().available(), dec!(0));
+
+    // uncovered sells risk (contract_price - price) * quantity
+    assert_eq!(
+        pie_ob.process_limit_order_with_options(2, 0, Side::Sell, dec!(5), dec!(5), account(2)),
+        Err(E::InsufficientCollateral)
+    );
+    assert_eq!(
+        pie_ob
+            .process_limit_order_with_options(2, 0, Side::Sell, dec!(5), dec!(4), account(2))
+            .unwrap()
+            .matches
+            .len(),
+        2
+    );
+    assert_eq!(
+        pie_ob.get_account(1).unwrap().positions,
+        vec![dec!(4), dec!(0), dec!(0)]
+    );
+    assert_eq!(pie_ob.get_account(2).unwrap().available(), dec!(0));
+
+    // sells covered by held contracts do not need collateral
+    assert_eq!(
+        pie_ob
+            .process_limit_order_with_options(3, 0, Side::Sell, dec!(9), dec!(4), account(1))
+            .unwrap()
+            .matches
+            .len(),
+        0
+    );
+    assert_eq!(
+        pie_ob.get_account(1).unwrap().reserved,
+        vec![dec!(4), dec!(0), dec!(0)]
+    );
+
+    // reserved contracts can not cover another sell
+    assert_eq!(
+        pie_ob.process_limit_order_with_options(4, 0, Side::Sell, dec!(9), dec!(1), account(1)),
+        Err(E::InsufficientCollateral)
+    );
+
+    pie_ob.cancel_order(3).unwrap();
+    assert_eq!(
+        pie_ob.get_account(1).unwrap().reserved,
+        vec![dec!(0), dec!(0), dec!(0)]
+    );
+}
+
+#[test]
+fn process_market_order1() {
+    let mut pie_ob = PieOrderBook::new(dec!(10), 3).unwrap();
+
+    // empty order book
+    assert_eq!(
+        pie_ob
+            .process_market_order(1, 0, Side::Buy, dec!(5), None)
+            .unwrap(),
+        Execution {
+            matches: vec![],
+            fills: vec![],
+            unfilled: dec!(5),
+            cancelled: vec![],
+            fees: vec![],
+        }
+    );
+    assert!(pie_ob.get_order(1).is_none());
+
+    assert_eq!(
+        pie_ob
+            .process_limit_order(2, 0, Side::Sell, dec!(6), dec!(2))
+            .unwrap()
+            .len(),
+        0
+    );
+    assert_eq!(
+        pie_ob
+            .process_limit_order(3, 1, Side::Buy, dec!(3), dec!(3))
+            .unwrap()
+            .len(),
+        0
+    );
+    assert_eq!(
+        pie_ob
+            .process_limit_order(4, 2, Side::Buy, dec!(2), dec!(1))
+            .unwrap()
+            .len(),
+        0
+    );
+
+    // sweeps the complement at 5 then own book at 6
+    let mut execution = pie_ob
+        .process_market_order(5, 0, Side::Buy, dec!(4), None)
+        .unwrap();
+    execution.matches.sort_by_key(|v| v.order);
+    assert_eq!(execution.unfilled, dec!(1));
+    assert_eq!(
+        execution.matches,
+        vec![
+            OrderMatch {
+                order: 2,
+                quantity: dec!(2),
+                cost: dec!(-12)
+            },
+            OrderMatch {
+                order: 3,
+                quantity: dec!(1),
+                cost: dec!(3)
+            },
+            OrderMatch {
+                order: 4,
+                quantity: dec!(1),
+                cost: dec!(2)
+            },
+            OrderMatch {
+                order: 5,
+                quantity: dec!(3),
+                cost: dec!(17)
+            },
+        ]
+    );
+    assert!(pie_ob.get_order(5).is_none());
+}
+
+#[test]
+fn process_market_order2() {
+    let mut pie_ob = PieOrderBook::new(dec!(10), 2).unwrap();
+
+    assert_eq!(
+        pie_ob
+            .process_limit_order(1, 0, Side::Buy, dec!(6), dec!(2))
+            .unwrap()
+            .len(),
+        0
+    );
+    assert_eq!(
+        pie_ob
+            .process_limit_order(2, 0, Side::Buy, dec!(4), dec!(2))
+            .unwrap()
+            .len(),
+        0
+    );
+
+    // worst price protection stops the sell at 5
+    let execution = pie_ob
+        .process_market_order(3, 0, Side::Sell, dec!(3), Some(dec!(5)))
+        .unwrap();
+    assert_eq!(execution.unfilled, dec!(1));
+    assert_eq!(pie_ob.get_order(2).unwrap().quantity, dec!(2));
+
+    use rust_pie_ob::errors::ProcessMarketOrder as E;
+    assert_eq!(
+        pie_ob.process_market_order(4, 0, Side::Sell, dec!(3), Some(dec!(10))),
+        Err(E::OrderValidationFailed)
+    );
+    assert_eq!(
+        pie_ob.process_market_order(4, 0, Side::Sell, dec!(0), None),
+        Err(E::OrderValidationFailed)
+    );
+    assert_eq!(
+        pie_ob.process_market_order(2, 0, Side::Sell, dec!(1), None),
+        Err(E::DuplicateOrderId)
+    );
+}
+
+#[test]
+fn time_in_force1() {
+    let mut pie_ob = PieOrderBook::new(dec!(10), 3).unwrap();
+
+    let time_in_force = |time_in_force| OrderOptions {
+        time_in_force,
+        ..Default::default()
+    };
+
+    assert_eq!(
+        pie_ob
+            .process_limit_order(1, 0, Side::Sell, dec!(6), dec!(2))
+            .unwrap()
+            .len(),
+        0
+    );
+    assert_eq!(
+        pie_ob
+            .process_limit_order(2, 1, Side::Buy, dec!(3), dec!(3))
+            .unwrap()
+            .len(),
+        0
+    );
+    assert_eq!(
+        pie_ob
+            .process_limit_order(3, 2, Side::Buy, dec!(2), dec!(1))
+            .unwrap()
+            .len(),
+        0
+    );
+
+    // only 3 can be filled at 6 or better: 1 through the complement, 2 in own book
+    assert_eq!(
+        pie_ob
+            .process_limit_order_with_options(
+                4,
+                0,
+                Side::Buy,
+                dec!(6),
+                dec!(4),
+                time_in_force(TimeInForce::FillOrKill)
+            )
+            .map(|v| v.matches),
+        Ok(vec![])
+    );
+    assert!(pie_ob.get_order(4).is_none());
+    assert_eq!(pie_ob.get_order(1).unwrap().quantity, dec!(2));
+    assert_eq!(pie_ob.get_order(3).unwrap().quantity, dec!(1));
+
+    // complement at 5 only
+    assert_eq!(
+        pie_ob
+            .process_limit_order_with_options(
+                5,
+                0,
+                Side::Buy,
+                dec!(5),
+                dec!(2),
+                time_in_force(TimeInForce::FillOrKill)
+            )
+            .map(|v| v.matches),
+        Ok(vec![])
+    );
+
+    let res = pie_ob
+        .process_limit_order_with_options(
+            6,
+            0,
+            Side::Buy,
+            dec!(6),
+            dec!(3),
+            time_in_force(TimeInForce::FillOrKill),
+        )
+        .unwrap();
+    assert_eq!(res.matches.len(), 4);
+    assert!(pie_ob.get_order(1).is_none());
+    assert!(pie_ob.get_order(3).is_none());
+
+    // immediate or cancel drops what can not be filled
+    let res = pie_ob
+        .process_limit_order_with_options(
+            7,
+            1,
+            Side::Sell,
+            dec!(3),
+            dec!(5),
+            time_in_force(TimeInForce::ImmediateOrCancel),
+        )
+        .unwrap();
+    assert_eq!(res.unfilled, dec!(3));
+    assert_eq!(
+        res.matches.iter().find(|v| v.order == 7).unwrap().quantity,
+        dec!(2)
+    );
+    assert!(pie_ob.get_order(7).is_none());
+    assert!(pie_ob.get_order(2).is_none());
+}
+
+#[test]
+fn post_only1() {
+    use rust_pie_ob::errors::ProcessLimitOrder as E;
+
+    let mut pie_ob = PieOrderBook::new(dec!(10), 3).unwrap();
+
+    let post_only = |post_only| OrderOptions {
+        post_only: Some(post_only),
+        ..Default::default()
+    };
+
+    assert_eq!(
+        pie_ob
+            .process_limit_order(1, 0, Side::Sell, dec!(6), dec!(2))
+            .unwrap()
+            .len(),
+        0
+    );
+    assert_eq!(
+        pie_ob
+            .process_limit_order(2, 1, Side::Buy, dec!(3), dec!(3))
+            .unwrap()
+            .len(),
+        0
+    );
+
+    // does not match while outcome 2 has no bids
+    assert_eq!(
+        pie_ob
+            .process_limit_order_with_options(
+                3,
+                0,
+                Side::Buy,
+                dec!(5.5),
+                dec!(1),
+                post_only(PostOnly::Reject)
+            )
+            .map(|v| v.matches),
+        Ok(vec![])
+    );
+    assert_eq!(pie_ob.get_order(3).unwrap().price, dec!(5.5));
+    pie_ob.cancel_order(3).unwrap();
+
+    assert_eq!(
+        pie_ob
+            .process_limit_order(4, 2, Side::Buy, dec!(2), dec!(1))
+            .unwrap()
+            .len(),
+        0
+    );
+
+    // would match own book at 6
+    assert_eq!(
+        pie_ob.process_limit_order_with_options(
+            5,
+            0,
+            Side::Buy,
+            dec!(6),
+            dec!(1),
+            post_only(PostOnly::Reject)
+        ),
+        Err(E::PostOnlyWouldMatch)
+    );
+    // would match through the complement at 5
+    assert_eq!(
+        pie_ob.process_limit_order_with_options(
+            5,
+            0,
+            Side::Buy,
+            dec!(5),
+            dec!(1),
+            post_only(PostOnly::Reject)
+        ),
+        Err(E::PostOnlyWouldMatch)
+    );
+    assert!(pie_ob.get_order(5).is_none());
+
+    // repricing needs a tick size
+    assert_eq!(
+        pie_ob.process_limit_order_with_options(
+            5,
+            0,
+            Side::Buy,
+            dec!(6),
+            dec!(1),
+            post_only(PostOnly::Reprice)
+        ),
+        Err(E::OrderValidationFailed)
+    );
+    pie_ob
+        .set_trading_rules(TradingRules {
+            tick_size: Some(dec!(0.1)),
+            ..Default::default()
+        })
+        .unwrap();
+
+    // repriced below the complement price
+    assert_eq!(
+        pie_ob
+            .process_limit_order_with_options(
+                5,
+                0,
+                Side::Buy,
+                dec!(6),
+                dec!(1),
+                post_only(PostOnly::Reprice)
+            )
+            .map(|v| v.matches),
+        Ok(vec![])
+    );
+    assert_eq!(pie_ob.get_order(5).unwrap().price, dec!(4.9));
+
+    // repriced above the best bid
+    assert_eq!(
+        pie_ob
+            .process_limit_order_with_options(
+                6,
+                1,
+                Side::Sell,
+                dec!(2.5),
+                dec!(1),
+                post_only(PostOnly::Reprice)
+            )
+            .map(|v| v.matches),
+        Ok(vec![])
+    );
+    assert_eq!(pie_ob.get_order(6).unwrap().price, dec!(3.1));
+}
+
+#[test]
+fn fills1() {
+    let mut pie_ob = PieOrderBook::new(dec!(10), 3).unwrap();
+
+    pie_ob
+        .process_limit_order(1, 0, Side::Sell, dec!(4), dec!(2))
+        .unwrap();
+    pie_ob
+        .process_limit_order(2, 1, Side::Buy, dec!(3), dec!(5))
+        .unwrap();
+    pie_ob
+        .process_limit_order(3, 2, Side::Buy, dec!(2), dec!(5))
+        .unwrap();
+
+    let res = pie_ob
+        .process_limit_order_with_options(4, 0, Side::Buy, dec!(5), dec!(5), Default::default())
+        .unwrap();
+    assert_eq!(res.unfilled, dec!(0));
+    assert_eq!(
+        res.fills,
+        vec![
+            Fill {
+                maker: 1,
+                taker: 4,
+                outcome: 0,
+                price: dec!(4),
+                quantity: dec!(2),
+                match_type: MatchType::Direct,
+            },
+            Fill {
+                maker: 2,
+                taker: 4,
+                outcome: 1,
+                price: dec!(3),
+                quantity: dec!(3),
+                match_type: MatchType::Mint,
+            },
+            Fill {
+                maker: 3,
+                taker: 4,
+                outcome: 2,
+                price: dec!(2),
+                quantity: dec!(3),
+                match_type: MatchType::Mint,
+            },
+        ]
+    );
+}
+
+#[test]
+fn depth1() {
+    let mut pie_ob = PieOrderBook::new(dec!(10), 3).unwrap();
+
+    pie_ob
+        .process_limit_order(1, 0, Side::Buy, dec!(2), dec!(5))
+        .unwrap();
+    pie_ob
+        .process_limit_order(2, 0, Side::Buy, dec!(2), dec!(3))
+        .unwrap();
+    pie_ob
+        .process_limit_order(3, 0, Side::Buy, dec!(1), dec!(4))
+        .unwrap();
+    pie_ob
+        .process_limit_order(4, 0, Side::Sell, dec!(8), dec!(2))
+        .unwrap();
+    pie_ob
+        .process_limit_order(5, 1, Side::Buy, dec!(3), dec!(4))
+        .unwrap();
+    pie_ob
+        .process_limit_order(6, 2, Side::Buy, dec!(4), dec!(1))
+        .unwrap();
+    pie_ob
+        .process_limit_order(7, 2, Side::Buy, dec!(2), dec!(6))
+        .unwrap();
+
+    assert_eq!(pie_ob.depth(3, 5), None);
+    assert_eq!(
+        pie_ob.depth(0, 5),
+        Some(Depth {
+            bids: vec![
+                PriceLevel {
+                    price: dec!(2),
+                    quantity: dec!(8)
+                },
+                PriceLevel {
+                    price: dec!(1),
+                    quantity: dec!(4)
+                },
+            ],
+            asks: vec![PriceLevel {
+                price: dec!(8),
+                quantity: dec!(2)
+            }],
+        })
+    );
+    assert_eq!(pie_ob.depth(0, 1).unwrap().bids.len(), 1);
+
+    // bids of outcomes 1 and 2 imply asks of outcome 0 at 10 - 3 - 4 and 10 - 3 - 2
+    assert_eq!(
+        pie_ob.effective_depth(0, 5).unwrap().asks,
+        vec![
+            PriceLevel {
+                price: dec!(3),
+                quantity: dec!(1)
+            },
+            PriceLevel {
+                price: dec!(5),
+                quantity: dec!(3)
+            },
+            PriceLevel {
+                price: dec!(8),
+                quantity: dec!(2)
+            },
+        ]
+    );
+    assert_eq!(
+        pie_ob.effective_depth(0, 5).unwrap().bids,
+        pie_ob.depth(0, 5).unwrap().bids
+    );
+    assert_eq!(pie_ob.effective_depth(0, 2).unwrap().asks.len(), 2);
+
+    // levels follow fills, reductions and cancellations
+    pie_ob
+        .process_limit_order(8, 0, Side::Sell, dec!(2), dec!(6))
+        .unwrap();
+    pie_ob.amend_order(3, dec!(1), dec!(1)).unwrap();
+    pie_ob.cancel_order(4).unwrap();
+    assert_eq!(
+        pie_ob.depth(0, 5),
+        Some(Depth {
+            bids: vec![
+                PriceLevel {
+                    price: dec!(2),
+                    quantity: dec!(2)
+                },
+                PriceLevel {
+                    price: dec!(1),
+                    quantity: dec!(1)
+                },
+            ],
+            asks: vec![],
+        })
+    );
+}
+
+#[test]
+fn quote1() {
+    let mut pie_ob = PieOrderBook::new(dec!(10), 3).unwrap();
+
+    pie_ob
+        .process_limit_order(1, 0, Side::Buy, dec!(2), dec!(5))
+        .unwrap();
+    pie_ob
+        .process_limit_order(2, 0, Side::Sell, dec!(3), dec!(2))
+        .unwrap();
+    pie_ob
+        .process_limit_order(3, 1, Side::Buy, dec!(3), dec!(4))
+        .unwrap();
+    pie_ob
+        .process_limit_order(4, 2, Side::Buy, dec!(4), dec!(1))
+        .unwrap();
+    pie_ob
+        .process_limit_order(5, 1, Side::Sell, dec!(6), dec!(3))
+        .unwrap();
+    pie_ob
+        .process_limit_order(6, 2, Side::Sell, dec!(5), dec!(7))
+        .unwrap();
+
+    let level = |price, quantity| Some(PriceLevel { price, quantity });
+
+    assert_eq!(pie_ob.quote(3), None);
+    assert_eq!(
+        pie_ob.quote(0),
+        Some(Quote {
+            outcome: 0,
+            bid: level(dec!(2), dec!(5)),
+            ask: level(dec!(3), dec!(2)),
+            implied_bid: None,
+            implied_ask: level(dec!(3), dec!(1)),
+            best_bid: level(dec!(2), dec!(5)),
+            best_ask: level(dec!(3), dec!(3)),
+        })
+    );
+
+    // asks of outcomes 0 and 2 imply a bid of outcome 1 at 10 - 3 - 5
+    let quote = pie_ob.quote(1).unwrap();
+    assert_eq!(quote.implied_bid, level(dec!(2), dec!(2)));
+    assert_eq!(quote.best_bid, level(dec!(3), dec!(4)));
+    assert_eq!(quote.implied_ask, level(dec!(4), dec!(1)));
+    assert_eq!(quote.best_ask, level(dec!(4), dec!(1)));
+
+    assert_eq!(pie_ob.quotes().len(), 3);
+    assert_eq!(pie_ob.quotes()[2], pie_ob.quote(2).unwrap());
+}
+
+#[test]
+fn probabilities1() {
+    let mut pie_ob = PieOrderBook::new(dec!(10), 3).unwrap();
+
+    let probabilities = pie_ob.probabilities();
+    assert_eq!(probabilities.overround, dec!(0.5));
+    for outcome in probabilities.outcomes.iter() {
+        assert_eq!(outcome.mid, dec!(5));
+        assert_eq!(outcome.raw, dec!(0.5));
+        assert_eq!(outcome.normalized.round_dp(6), dec!(0.333333));
+    }
+
+    pie_ob
+        .process_limit_order(1, 0, Side::Buy, dec!(5), dec!(1))
+        .unwrap();
+    pie_ob
+        .process_limit_order(2, 0, Side::Sell, dec!(7), dec!(1))
+        .unwrap();
+    pie_ob
+        .process_limit_order(3, 1, Side::Buy, dec!(2), dec!(1))
+        .unwrap();
+    pie_ob
+        .process_limit_order(4, 1, Side::Sell, dec!(4), dec!(1))
+        .unwrap();
+    pie_ob
+        .process_limit_order(5, 2, Side::Buy, dec!(1), dec!(1))
+        .unwrap();
+    pie_ob
+        .process_limit_order(6, 2, Side::Sell, dec!(2), dec!(1))
+        .unwrap();
+
+    let probabilities = pie_ob.probabilities();
+    assert_eq!(
+        probabilities
+            .outcomes
+            .iter()
+            .map(|outcome| outcome.mid)
+            .collect::<Vec<_>>(),
+        vec![dec!(6), dec!(3), dec!(1.5)]
+    );
+    assert_eq!(probabilities.outcomes[0].raw, dec!(0.6));
+    assert_eq!(probabilities.overround, dec!(0.05));
+    assert_eq!(
+        probabilities
+            .outcomes
+            .iter()
+            .map(|outcome| outcome.normalized)
+            .sum::<rust_decimal::Decimal>()
+            .round_dp(6),
+        dec!(1)
+    );
+}
+
+#[test]
+fn events1() {
+    let mut pie_ob = PieOrderBook::new(dec!(10), 2).unwrap();
+    let (sender, receiver) = std::sync::mpsc::channel();
+    pie_ob.set_event_sink(sender);
+
+    pie_ob
+        .process_limit_order(1, 0, Side::Sell, dec!(4), dec!(5))
+        .unwrap();
+    let events: Vec<_> = receiver.try_iter().collect();
+    assert_eq!(events.len(), 3);
+    assert!(matches!(
+        events[0],
+        Event::OrderAccepted {
+            order: 1,
+            outcome: 0,
+            side: Side::Sell,
+            ..
+        }
+    ));
+    assert!(matches!(
+        events[1],
+        Event::OrderRested { order: 1, quantity, .. } if quantity == dec!(5)
+    ));
+    assert!(matches!(
+        events[2],
+        Event::BookLevelChanged { outcome: 0, side: Side::Sell, price, quantity }
+            if price == dec!(4) && quantity == dec!(5)
+    ));
+
+    pie_ob
+        .process_market_order(2, 0, Side::Buy, dec!(2), None)
+        .unwrap();
+    let events: Vec<_> = receiver.try_iter().collect();
+    assert_eq!(events.len(), 3);
+    assert!(matches!(
+        events[0],
+        Event::OrderAccepted {
+            order: 2,
+            price: None,
+            ..
+        }
+    ));
+    assert!(matches!(
+        events[1],
+        Event::Fill(Fill { maker: 1, taker: 2, quantity, .. }) if quantity == dec!(2)
+    ));
+    assert!(matches!(
+        events[2],
+        Event::BookLevelChanged { outcome: 0, side: Side::Sell, quantity, .. } if quantity == dec!(3)
+    ));
+
+    pie_ob.cancel_order(1).unwrap();
+    let events: Vec<_> = receiver.try_iter().collect();
+    assert_eq!(events.len(), 2);
+    assert!(matches!(
+        events[0],
+        Event::OrderCancelled { order: 1, quantity, .. } if quantity == dec!(3)
+    ));
+    assert!(matches!(
+        events[1],
+        Event::BookLevelChanged { quantity, .. } if quantity.is_zero()
+    ));
+
+    pie_ob
+        .process_limit_order(3, 1, Side::Buy, dec!(3), dec!(1))
+        .unwrap();
+    receiver.try_iter().count();
+    pie_ob.resolve(1).unwrap();
+    let events: Vec<_> = receiver.try_iter().collect();
+    assert_eq!(events.len(), 3);
+    assert!(matches!(events[0], Event::OrderCancelled { order: 3, .. }));
+    assert!(matches!(
+        events[2],
+        Event::MarketResolved(Resolution::Outcome(1))
+    ));
+}
+
+#[test]
+fn events2() {
+    let mut pie_ob: PieOrderBook<u32, u32> = PieOrderBook::with_accounts(dec!(10), 2).unwrap();
+    let (sender, receiver) = std::sync::mpsc::channel();
+    pie_ob.set_event_sink(sender);
+
+    pie_ob.deposit(1, dec!(100)).unwrap();
+    pie_ob.mint_complete_set(1, dec!(3)).unwrap();
+    pie_ob.merge_complete_set(1, dec!(1)).unwrap();
+    pie_ob.withdraw(1, dec!(50)).unwrap();
+    let events: Vec<_> = receiver.try_iter().collect();
+    assert!(matches!(
+        events[..],
+        [
+            Event::Deposited { account: 1, amount: a },
+            Event::CompleteSetsMinted { account: 1, quantity: m },
+            Event::CompleteSetsMerged { account: 1, quantity: g },
+            Event::Withdrawn { account: 1, amount: w },
+        ] if a == dec!(100) && m == dec!(3) && g == dec!(1) && w == dec!(50)
+    ));
+
+    // stop and queued orders are cancelled like resting orders
+    let stop_order = StopOrder {
+        outcome: 0,
+        side: Side::Buy,
+        quantity: dec!(1),
+        trigger: StopTrigger::LastTrade,
+        trigger_price: dec!(5),
+        limit_price: None,
+        options: OrderOptions::default(),
+    };
+    pie_ob.submit_stop_order(1, stop_order.clone()).unwrap();
+    pie_ob.cancel_stop_order(1).unwrap();
+    let events: Vec<_> = receiver.try_iter().collect();
+    assert!(matches!(events[..], [Event::StopCancelled { order: 1 }]));
+
+    pie_ob.submit_stop_order(2, stop_order).unwrap();
+    pie_ob.set_market_state(MarketState::Halted).unwrap();
+    pie_ob
+        .process_limit_order(3, 1, Side::Buy, dec!(3), dec!(1))
+        .unwrap();
+    receiver.try_iter().count();
+    pie_ob.resolve(1).unwrap();
+    let events: Vec<_> = receiver.try_iter().collect();
+    assert!(matches!(
+        events[..],
+        [
+            Event::StopCancelled { order: 2 },
+            Event::OrderCancelled { order: 3, .. },
+            Event::MarketResolved(_),
+        ]
+    ));
+}
+
+#[test]
+fn replay1() {
+    let mut pie_ob: PieOrderBook<u32, u32> = PieOrderBook::with_accounts(dec!(10), 3).unwrap();
+    let mut outputs = Vec::new();
+    let mut record = |pie_ob: &mut PieOrderBook<u32, u32>, command| {
+        if let Ok(output) = pie_ob.apply(command) {
+            outputs.push(output);
+        }
+    };
+    record(
+        &mut pie_ob,
+        Command::Deposit {
+            account: 1,
+            amount: dec!(100),
+        },
+    );
+    record(
+        &mut pie_ob,
+        Command::Deposit {
+            account: 2,
+            amount: dec!(100),
+        },
+    );
+    let orders = [
+        (1, 0, Side::Buy, dec!(3), dec!(4), 1),
+        (2, 1, Side::Buy, dec!(2), dec!(3), 1),
+        (3, 1, Side::Buy, dec!(2), dec!(2), 2),
+        (4, 2, Side::Buy, dec!(4), dec!(5), 2),
+        (5, 0, Side::Sell, dec!(2), dec!(3), 2),
+        (6, 0, Side::Sell, dec!(5), dec!(6), 1),
+        // rejected, never logged
+        (6, 0, Side::Sell, dec!(5), dec!(6), 1),
+    ];
+    for (id, outcome, side, price, quantity, a) in orders {
+        record(
+            &mut pie_ob,
+            Command::LimitOrder {
+                id,
+                outcome,
+                side,
+                price,
+                quantity,
+                options: account(a),
+                now: 0,
+            },
+        );
+    }
+    record(&mut pie_ob, Command::CancelOrder { id: 4 });
+    record(
+        &mut pie_ob,
+        Command::MarketOrder {
+            id: 7,
+            outcome: 2,
+            side: Side::Buy,
+            quantity: dec!(4),
+            worst_price: None,
+            options: account(2),
+            now: 0,
+        },
+    );
+    record(
+        &mut pie_ob,
+        Command::Withdraw {
+            account: 1,
+            amount: dec!(10),
+        },
+    );
+    // settles several positions, accounts and resting orders
+    record(&mut pie_ob, Command::Resolve { winning_outcome: 0 });
+
+    assert_eq!(outputs.len(), 12);
+    assert_eq!(pie_ob.command_log().len(), 13);
+    assert!(matches!(
+        pie_ob.command_log()[0],
+        Command::New { outcomes: 3, .. }
+    ));
+
+    let mut log = pie_ob.command_log().iter().cloned();
+    let log_head = log.next().unwrap();
+    let mut replayed = PieOrderBook::replay([log_head]).unwrap();
+    for (command, output) in log.zip(outputs.iter()) {
+        assert_eq!(replayed.apply(command).as_ref(), Ok(output));
+    }
+
+    let replayed_all = PieOrderBook::replay(pie_ob.command_log().to_vec()).unwrap();
+    for replayed in [replayed, replayed_all] {
+        assert_eq!(replayed.to_string(), pie_ob.to_string());
+        assert_eq!(replayed.quotes(), pie_ob.quotes());
+        for a in [1, 2] {
+            assert_eq!(replayed.get_account(a), pie_ob.get_account(a));
+        }
+        for id in 1..=7 {
+            assert_eq!(
+                replayed.get_position(id).map(|p| (p.quantity, p.cost)),
+                pie_ob.get_position(id).map(|p| (p.quantity, p.cost))
+            );
+        }
+        assert_eq!(replayed.command_log().len(), pie_ob.command_log().len());
+    }
+
+    use rust_pie_ob::errors::Replay as E;
+    assert_eq!(
+        PieOrderBook::<u32, u32>::replay(pie_ob.command_log()[1..].to_vec()).err(),
+        Some(E::MissingNew)
+    );
+}
+
+#[test]
+fn snapshot1() {
+    let mut pie_ob: PieOrderBook<u32, u32> = PieOrderBook::with_accounts(dec!(10), 2).unwrap();
+    pie_ob.deposit(1, dec!(100)).unwrap();
+
+    for (id, price) in [(3, dec!(4)), (1, dec!(4)), (2, dec!(3)), (4, dec!(4))] {
+        pie_ob
+            .process_limit_order_with_options(id, 0, Side::Buy, price, dec!(2), account(1))
+            .unwrap();
+    }
+    pie_ob.cancel_order(1).unwrap();
+    pie_ob
+        .process_limit_order(5, 0, Side::Sell, dec!(4), dec!(1))
+        .unwrap();
+
+    let snapshot = pie_ob.snapshot();
+    assert_eq!(pie_ob.take_command_log().len(), 8);
+    assert!(pie_ob.command_log().is_empty());
+    let mut restored = PieOrderBook::restore(snapshot.clone()).unwrap();
+    assert_eq!(restored.to_string(), pie_ob.to_string());
+    assert_eq!(restored.get_account(1), pie_ob.get_account(1));
+    assert!(restored.command_log().is_empty());
+
+    // time priority is kept, order 3 is matched before order 4
+    let execution = pie_ob
+        .process_market_order(6, 0, Side::Sell, dec!(4), None)
+        .unwrap();
+    assert_eq!(
+        restored
+            .process_market_order(6, 0, Side::Sell, dec!(4), None)
+            .unwrap(),
+        execution
+    );
+    assert_eq!(
+        execution
+            .fills
+            .iter()
+            .map(|fill| fill.maker)
+            .collect::<Vec<_>>(),
+        vec![3, 4, 2]
+    );
+    assert_eq!(restored.get_account(1), pie_ob.get_account(1));
+
+    // the log after the snapshot reproduces the order book
+    assert_eq!(pie_ob.command_log().len(), 1);
+    let mut reapplied = PieOrderBook::restore(snapshot).unwrap();
+    for command in pie_ob.take_command_log() {
+        reapplied.apply(command).unwrap();
+    }
+    assert_eq!(reapplied.to_string(), pie_ob.to_string());
+    assert_eq!(reapplied.get_account(1), pie_ob.get_account(1));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn snapshot2() {
+    let mut pie_ob = PieOrderBook::new(dec!(10), 3).unwrap();
+    pie_ob
+        .process_limit_order(1, 0, Side::Buy, dec!(4), dec!(2))
+        .unwrap();
+    pie_ob
+        .process_limit_order(2, 1, Side::Sell, dec!(5), dec!(3))
+        .unwrap();
+
+    let json = serde_json::to_string(&pie_ob).unwrap();
+    let restored: PieOrderBook<u32> = serde_json::from_str(&json).unwrap();
+    assert_eq!(restored.to_string(), pie_ob.to_string());
+    assert_eq!(serde_json::to_string(&restored).unwrap(), json);
+
+    let snapshot: rust_pie_ob::Snapshot<u32> = serde_json::from_str(&json).unwrap();
+    assert!(PieOrderBook::restore(snapshot).is_ok());
+
+    // binary formats that do not describe their own types
+    let bytes = bincode::serialize(&pie_ob).unwrap();
+    let restored: PieOrderBook<u32> = bincode::deserialize(&bytes).unwrap();
+    assert_eq!(restored.to_string(), pie_ob.to_string());
+    assert_eq!(bincode::serialize(&restored).unwrap(), bytes);
+
+    let mut pie_ob: PieOrderBook<u32, u32> = PieOrderBook::with_accounts(dec!(10), 3).unwrap();
+    pie_ob.deposit(1, dec!(100)).unwrap();
+    for (id, outcome, price) in [(1, 0, dec!(4)), (2, 1, dec!(3)), (3, 2, dec!(2))] {
+        pie_ob
+            .process_limit_order_with_options(id, outcome, Side::Buy, price, dec!(2), account(1))
+            .unwrap();
+    }
+    let value = serde_json::to_value(&pie_ob).unwrap();
+
+    // orders of accounts in the order they were placed
+    let order_ids: Vec<_> = value["order_accounts"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|order| order[0].as_u64().unwrap())
+        .collect();
+    assert_eq!(order_ids, vec![1, 2, 3]);
+
+    use rust_pie_ob::errors::Restore as E;
+    let restore = |tamper: &dyn Fn(&mut serde_json::Value)| {
+        let mut value = value.clone();
+        tamper(&mut value);
+        let snapshot: rust_pie_ob::Snapshot<u32, u32> = serde_json::from_value(value).unwrap();
+        PieOrderBook::restore(snapshot).err()
+    };
+    assert_eq!(restore(&|_| {}), None);
+    assert_eq!(
+        restore(&|v| v["order_accounts"][0][1]["outcome"] = 3.into()),
+        Some(E::InvalidOrder)
+    );
+    assert_eq!(
+        restore(&|v| v["order_accounts"][0][1]["account"] = 2.into()),
+        Some(E::InvalidAccount)
+    );
+    assert_eq!(
+        restore(&|v| {
+            v["accounts"][0][1]["reserved"]
+                .as_array_mut()
+                .unwrap()
+                .pop();
+        }),
+        Some(E::InvalidAccount)
+    );
+    assert_eq!(
+        restore(&|v| {
+            v["halted"].as_array_mut().unwrap().pop();
+        }),
+        Some(E::OutcomesMismatch)
+    );
+}
+
+#[test]
+fn amend_order1() {
+    let mut pie_ob: PieOrderBook<u32, u32> = PieOrderBook::with_accounts(dec!(10), 2).unwrap();
+    pie_ob.deposit(1, dec!(24)).unwrap();
+
+    for id in 1..=3 {
+        pie_ob
+            .process_limit_order_with_options(id, 0, Side::Buy, dec!(4), dec!(2), account(1))
+            .unwrap();
+    }
+    assert_eq!(pie_ob.get_account(1).unwrap().locked, dec!(24));
+
+    use rust_pie_ob::errors::AmendOrder as E;
+    assert_eq!(
+        pie_ob.amend_order(4, dec!(4), dec!(1)),
+        Err(E::UnknownOrder)
+    );
+    assert_eq!(
+        pie_ob.amend_order(1, dec!(10), dec!(1)),
+        Err(E::OrderValidationFailed)
+    );
+    assert_eq!(
+        pie_ob.amend_order(1, dec!(4), dec!(0)),
+        Err(E::OrderValidationFailed)
+    );
+
+    // reducing keeps time priority and releases collateral
+    let res = pie_ob.amend_order(1, dec!(4), dec!(1)).unwrap();
+    assert_eq!(res.unfilled, dec!(1));
+    assert_eq!(pie_ob.get_order(1).unwrap().quantity, dec!(1));
+    assert_eq!(pie_ob.get_account(1).unwrap().locked, dec!(20));
+
+    // increasing over the available collateral leaves the order unchanged
+    assert_eq!(
+        pie_ob.amend_order(2, dec!(5), dec!(6)),
+        Err(E::InsufficientCollateral)
+    );
+    assert_eq!(pie_ob.get_order(2).unwrap().quantity, dec!(2));
+    assert_eq!(pie_ob.get_account(1).unwrap().locked, dec!(20));
+
+    // repricing loses time priority
+    pie_ob.amend_order(2, dec!(3), dec!(2)).unwrap();
+    pie_ob.amend_order(2, dec!(4), dec!(2)).unwrap();
+    assert_eq!(pie_ob.get_account(1).unwrap().locked, dec!(20));
+
+    let res = pie_ob
+        .process_market_order(5, 0, Side::Sell, dec!(5), None)
+        .unwrap();
+    assert_eq!(
+        res.fills
+            .iter()
+            .map(|fill| (fill.maker, fill.quantity))
+            .collect::<Vec<_>>(),
+        vec![(1, dec!(1)), (3, dec!(2)), (2, dec!(2))]
+    );
+}
+
+#[test]
+fn amend_order2() {
+    let mut pie_ob = PieOrderBook::new(dec!(10), 3).unwrap();
+
+    pie_ob
+        .process_limit_order(1, 1, Side::Buy, dec!(3), dec!(5))
+        .unwrap();
+    pie_ob
+        .process_limit_order(2, 2, Side::Buy, dec!(4), dec!(5))
+        .unwrap();
+    pie_ob
+        .process_limit_order(3, 0, Side::Buy, dec!(2), dec!(4))
+        .unwrap();
+
+    // repricing matches through the other outcome order books
+    let res = pie_ob.amend_order(3, dec!(3), dec!(4)).unwrap();
+    assert_eq!(res.unfilled, dec!(0));
+    assert!(res
+        .fills
+        .iter()
+        .all(|fill| matches!(fill.match_type, MatchType::Mint)));
+    assert!(pie_ob.get_order(3).is_none());
+    assert_eq!(pie_ob.get_position(3).unwrap().quantity, dec!(4));
+    assert_eq!(pie_ob.get_order(1).unwrap().quantity, dec!(1));
+    assert!(matches!(
+        pie_ob.command_log().last(),
+        Some(Command::AmendOrder { id: 3, .. })
+    ));
+
+    // post only orders stay post only
+    let post_only = OrderOptions {
+        post_only: Some(PostOnly::Reject),
+        ..Default::default()
+    };
+    pie_ob
+        .process_limit_order_with_options(4, 0, Side::Sell, dec!(6), dec!(1), post_only)
+        .unwrap();
+    pie_ob
+        .process_limit_order(5, 0, Side::Buy, dec!(2), dec!(1))
+        .unwrap();
+    use rust_pie_ob::errors::AmendOrder as E;
+    assert_eq!(
+        pie_ob.amend_order(4, dec!(2), dec!(1)).err(),
+        Some(E::PostOnlyWouldMatch)
+    );
+    assert_eq!(pie_ob.get_order(4).unwrap().price, dec!(6));
+    assert!(pie_ob
+        .amend_order(4, dec!(5), dec!(1))
+        .unwrap()
+        .matches
+        .is_empty());
+    assert_eq!(pie_ob.get_order(4).unwrap().price, dec!(5));
+}
+
+#[test]
+fn self_trade_prevention1() {
+    let mut pie_ob: PieOrderBook<u32, u32> = PieOrderBook::with_accounts(dec!(10), 3).unwrap();
+    pie_ob.deposit(1, dec!(100)).unwrap();
+    pie_ob.deposit(2, dec!(100)).unwrap();
+    let options = |a, self_trade_prevention| OrderOptions {
+        self_trade_prevention,
+        ..account(a)
+    };
+
+    pie_ob
+        .process_limit_order_with_options(1, 0, Side::Sell, dec!(4), dec!(2), options(1, None))
+        .unwrap();
+    pie_ob
+        .process_limit_order_with_options(2, 0, Side::Sell, dec!(4), dec!(2), options(2, None))
+        .unwrap();
+
+    let res = pie_ob
+        .process_limit_order_with_options(
+            3,
+            0,
+            Side::Buy,
+            dec!(4),
+            dec!(3),
+            options(1, Some(SelfTradePrevention::CancelOldest)),
+        )
+        .unwrap();
+    assert_eq!(res.cancelled, vec![1]);
+    assert_eq!(
+        res.fills.iter().map(|fill| fill.maker).collect::<Vec<_>>(),
+        vec![2]
+    );
+    assert_eq!(res.unfilled, dec!(1));
+    assert!(pie_ob.get_order(1).is_none());
+    assert_eq!(pie_ob.get_order(3).unwrap().quantity, dec!(1));
+
+    pie_ob
+        .process_limit_order_with_options(4, 0, Side::Sell, dec!(5), dec!(2), options(1, None))
+        .unwrap();
+    let res = pie_ob
+        .process_limit_order_with_options(
+            5,
+            0,
+            Side::Buy,
+            dec!(5),
+            dec!(1),
+            options(1, Some(SelfTradePrevention::CancelNewest)),
+        )
+        .unwrap();
+    assert_eq!(res.cancelled, vec![5]);
+    assert!(res.fills.is_empty());
+    assert!(pie_ob.get_order(5).is_none());
+    assert_eq!(pie_ob.get_order(4).unwrap().quantity, dec!(2));
+
+    // fill or kill orders do not count resting orders self trade prevention cancels
+    let mut pie_ob: PieOrderBook<u32, u32> = PieOrderBook::with_accounts(dec!(10), 2).unwrap();
+    pie_ob.deposit(1, dec!(100)).unwrap();
+    pie_ob.deposit(2, dec!(100)).unwrap();
+    pie_ob
+        .process_limit_order_with_options(1, 0, Side::Sell, dec!(4), dec!(2), options(1, None))
+        .unwrap();
+    pie_ob
+        .process_limit_order_with_options(2, 0, Side::Sell, dec!(4), dec!(2), options(2, None))
+        .unwrap();
+    let fill_or_kill = || OrderOptions {
+        time_in_force: TimeInForce::FillOrKill,
+        ..options(1, Some(SelfTradePrevention::CancelOldest))
+    };
+
+    let res = pie_ob
+        .process_limit_order_with_options(3, 0, Side::Buy, dec!(4), dec!(4), fill_or_kill())
+        .unwrap();
+    assert!(res.fills.is_empty() && res.cancelled.is_empty());
+    assert_eq!(res.unfilled, dec!(4));
+    assert_eq!(pie_ob.get_order(1).unwrap().quantity, dec!(2));
+
+    pie_ob
+        .process_limit_order_with_options(4, 0, Side::Sell, dec!(4), dec!(2), options(2, None))
+        .unwrap();
+    let res = pie_ob
+        .process_limit_order_with_options(5, 0, Side::Buy, dec!(4), dec!(4), fill_or_kill())
+        .unwrap();
+    assert_eq!(res.cancelled, vec![1]);
+    assert_eq!(
+        res.fills.iter().map(|fill| fill.maker).collect::<Vec<_>>(),
+        vec![2, 4]
+    );
+    assert_eq!(res.unfilled, dec!(0));
+}
+
+#[test]
+fn self_trade_prevention2() {
+    let mut pie_ob: PieOrderBook<u32, u32> = PieOrderBook::with_accounts(dec!(10), 3).unwrap();
+    pie_ob.deposit(1, dec!(100)).unwrap();
+    pie_ob.deposit(2, dec!(100)).unwrap();
+    let options = |a, self_trade_prevention| OrderOptions {
+        self_trade_prevention,
+        ..account(a)
+    };
+
+    pie_ob
+        .process_limit_order_with_options(1, 1, Side::Buy, dec!(3), dec!(2), options(1, None))
+        .unwrap();
+    pie_ob
+        .process_limit_order_with_options(2, 2, Side::Buy, dec!(4), dec!(5), options(2, None))
+        .unwrap();
+
+    // the complement path would match order 1 of the same account
+    let res = pie_ob
+        .process_limit_order_with_options(
+            3,
+            0,
+            Side::Buy,
+            dec!(3),
+            dec!(5),
+            options(1, Some(SelfTradePrevention::Decrement)),
+        )
+        .unwrap();
+    assert_eq!(res.cancelled, vec![1]);
+    assert!(res.fills.is_empty());
+    assert_eq!(res.unfilled, dec!(3));
+    assert_eq!(pie_ob.get_order(3).unwrap().quantity, dec!(3));
+    assert_eq!(pie_ob.get_account(1).unwrap().locked, dec!(9));
+
+    let res = pie_ob
+        .process_limit_order_with_options(
+            4,
+            1,
+            Side::Buy,
+            dec!(3),
+            dec!(1),
+            options(2, Some(SelfTradePrevention::CancelBoth)),
+        )
+        .unwrap();
+    assert_eq!(res.cancelled, vec![2, 4]);
+    assert!(res.fills.is_empty());
+    assert!(pie_ob.get_order(2).is_none());
+    assert!(pie_ob.get_order(4).is_none());
+    assert_eq!(pie_ob.get_order(3).unwrap().quantity, dec!(3));
+    assert_eq!(pie_ob.get_account(2).unwrap().locked, dec!(0));
+}
+
+#[test]
+fn trading_rules1() {
+    let mut pie_ob = PieOrderBook::new(dec!(10), 3).unwrap();
+
+    use rust_pie_ob::errors::SetTradingRules as R;
+    assert_eq!(
+        pie_ob.set_trading_rules(TradingRules {
+            tick_size: Some(dec!(0)),
+            ..Default::default()
+        }),
+        Err(R::NonPositiveSize)
+    );
+    assert_eq!(
+        pie_ob.set_trading_rules(TradingRules {
+            tick_size: Some(dec!(3)),
+            ..Default::default()
+        }),
+        Err(R::ContractPriceOffTick)
+    );
+
+    pie_ob
+        .process_limit_order(1, 1, Side::Buy, dec!(2.25), dec!(1))
+        .unwrap();
+    let rules = TradingRules {
+        tick_size: Some(dec!(0.5)),
+        lot_size: Some(dec!(2)),
+        min_quantity: Some(dec!(4)),
+    };
+    assert_eq!(pie_ob.set_trading_rules(rules), Err(R::RestingOrderOffGrid));
+    pie_ob.cancel_order(1).unwrap();
+    pie_ob.set_trading_rules(rules).unwrap();
+    assert_eq!(pie_ob.get_trading_rules(), &rules);
+
+    use rust_pie_ob::errors::ProcessLimitOrder as E;
+    assert_eq!(
+        pie_ob.process_limit_order(2, 0, Side::Buy, dec!(2.25), dec!(4)),
+        Err(E::PriceOffTick)
+    );
+    assert_eq!(
+        pie_ob.process_limit_order(2, 0, Side::Buy, dec!(2.5), dec!(5)),
+        Err(E::QuantityOffLot)
+    );
+    assert_eq!(
+        pie_ob.process_limit_order(2, 0, Side::Buy, dec!(2.5), dec!(2)),
+        Err(E::QuantityBelowMinimum)
+    );
+
+    use rust_pie_ob::errors::ProcessMarketOrder as M;
+    assert_eq!(
+        pie_ob.process_market_order(2, 0, Side::Buy, dec!(4), Some(dec!(2.1))),
+        Err(M::PriceOffTick)
+    );
+
+    // implied prices of the complement path stay on the grid
+    pie_ob
+        .process_limit_order(2, 1, Side::Buy, dec!(2.5), dec!(4))
+        .unwrap();
+    pie_ob
+        .process_limit_order(3, 2, Side::Buy, dec!(4.5), dec!(4))
+        .unwrap();
+    let quote = pie_ob.quote(0).unwrap();
+    assert_eq!(quote.implied_ask.unwrap().price, dec!(3));
+
+    // post only orders are repriced by one tick
+    let res = pie_ob
+        .process_limit_order_with_options(
+            4,
+            0,
+            Side::Buy,
+            dec!(3),
+            dec!(4),
+            OrderOptions {
+                post_only: Some(PostOnly::Reprice),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    assert_eq!(res.unfilled, dec!(4));
+    assert_eq!(pie_ob.get_order(4).unwrap().price, dec!(2.5));
+}
+
+#[test]
+fn fees1() {
+    let mut pie_ob: PieOrderBook<u32, u32> = PieOrderBook::with_accounts(dec!(10), 2).unwrap();
+    pie_ob.deposit(1, dec!(100)).unwrap();
+    pie_ob.deposit(2, dec!(100)).unwrap();
+    let tier = FeeTier {
+        min_volume: dec!(2),
+        maker: FeeRate::Flat(dec!(0)),
+        taker: FeeRate::Flat(dec!(0.05)),
+    };
+    use rust_pie_ob::errors::SetFeeSchedule as E;
+    assert_eq!(
+        pie_ob.set_fee_schedule(Some(FeeSchedule {
+            maker: FeeRate::Bps(dec!(100)),
+            taker: FeeRate::PerContract(dec!(0.1)),
+            tiers: vec![tier.clone(), tier.clone()],
+        })),
+        Err(E::UnsortedTiers)
+    );
+    pie_ob
+        .set_fee_schedule(Some(FeeSchedule {
+            maker: FeeRate::Bps(dec!(100)),
+            taker: FeeRate::PerContract(dec!(0.1)),
+            tiers: vec![tier],
+        }))
+        .unwrap();
+
+    pie_ob
+        .process_limit_order_with_options(1, 0, Side::Sell, dec!(4), dec!(2), account(1))
+        .unwrap();
+    let res = pie_ob
+        .process_limit_order_with_options(2, 0, Side::Buy, dec!(4), dec!(2), account(2))
+        .unwrap();
+    assert_eq!(
+        res.fees,
+        vec![
+            FeeCharge {
+                order: 1,
+                liquidity: Liquidity::Maker,
+                amount: dec!(0.08)
+            },
+            FeeCharge {
+                order: 2,
+                liquidity: Liquidity::Taker,
+                amount: dec!(0.2)
+            },
+        ]
+    );
+    // costs do not include fees
+    assert_eq!(res.matches[1].cost, dec!(8));
+    assert_eq!(pie_ob.get_account(1).unwrap().cash, dec!(107.92));
+    assert_eq!(pie_ob.get_account(2).unwrap().cash, dec!(91.8));
+    assert_eq!(pie_ob.get_account(2).unwrap().volume, dec!(2));
+
+    // both accounts reached the tier
+    pie_ob
+        .process_limit_order_with_options(3, 0, Side::Sell, dec!(4), dec!(1), account(1))
+        .unwrap();
+    let res = pie_ob
+        .process_market_order_with_options(4, 0, Side::Buy, dec!(1), None, account(2))
+        .unwrap();
+    assert_eq!(
+        res.fees.iter().map(|fee| fee.amount).collect::<Vec<_>>(),
+        vec![dec!(0), dec!(0.05)]
+    );
+    assert_eq!(pie_ob.get_collected_fees(), dec!(0.33));
+
+    pie_ob.set_fee_schedule(None).unwrap();
+    pie_ob
+        .process_limit_order_with_options(5, 0, Side::Sell, dec!(4), dec!(1), account(1))
+        .unwrap();
+    let res = pie_ob
+        .process_limit_order_with_options(6, 0, Side::Buy, dec!(4), dec!(1), account(2))
+        .unwrap();
+    assert!(res.fees.is_empty());
+    assert_eq!(pie_ob.get_collected_fees(), dec!(0.33));
+
+    // the taker fee is part of the collateral of an order
+    pie_ob.deposit(3, dec!(5)).unwrap();
+    pie_ob
+        .set_fee_schedule(Some(FeeSchedule {
+            maker: FeeRate::Flat(dec!(0)),
+            taker: FeeRate::Flat(dec!(5)),
+            tiers: Vec::new(),
+        }))
+        .unwrap();
+    pie_ob
+        .process_limit_order_with_options(7, 0, Side::Sell, dec!(5), dec!(1), account(1))
+        .unwrap();
+    use rust_pie_ob::errors::ProcessLimitOrder as L;
+    assert_eq!(
+        pie_ob
+            .process_limit_order_with_options(8, 0, Side::Buy, dec!(5), dec!(1), account(3))
+            .err(),
+        Some(L::InsufficientCollateral)
+    );
+    pie_ob.deposit(3, dec!(5)).unwrap();
+    pie_ob
+        .process_limit_order_with_options(8, 0, Side::Buy, dec!(5), dec!(1), account(3))
+        .unwrap();
+    assert_eq!(pie_ob.get_account(3).unwrap().cash, dec!(0));
+
+    // the highest maker fee of resting orders is locked
+    assert_eq!(
+        pie_ob.set_fee_schedule(Some(FeeSchedule {
+            maker: FeeRate::Flat(dec!(5)),
+            taker: FeeRate::Flat(dec!(0)),
+            tiers: Vec::new(),
+        })),
+        Err(E::FlatMakerFee)
+    );
+    pie_ob
+        .set_fee_schedule(Some(FeeSchedule {
+            maker: FeeRate::PerContract(dec!(1)),
+            taker: FeeRate::Flat(dec!(0)),
+            tiers: Vec::new(),
+        }))
+        .unwrap();
+    pie_ob.deposit(4, dec!(10)).unwrap();
+    assert_eq!(
+        pie_ob
+            .process_limit_order_with_options(9, 0, Side::Buy, dec!(5), dec!(2), account(4))
+            .err(),
+        Some(L::InsufficientCollateral)
+    );
+    pie_ob.deposit(4, dec!(2)).unwrap();
+    pie_ob
+        .process_limit_order_with_options(9, 0, Side::Buy, dec!(5), dec!(2), account(4))
+        .unwrap();
+    assert_eq!(pie_ob.get_account(4).unwrap().locked, dec!(12));
+    assert_eq!(
+        pie_ob.set_fee_schedule(Some(FeeSchedule {
+            maker: FeeRate::PerContract(dec!(2)),
+            taker: FeeRate::Flat(dec!(0)),
+            tiers: Vec::new(),
+        })),
+        Err(E::InsufficientCollateral)
+    );
+    assert_eq!(pie_ob.get_account(4).unwrap().locked, dec!(12));
+
+    for id in [10, 11] {
+        pie_ob
+            .process_limit_order_with_options(id, 0, Side::Sell, dec!(5), dec!(1), account(1))
+            .unwrap();
+    }
+    let account = pie_ob.get_account(4).unwrap();
+    assert_eq!((account.cash, account.locked), (dec!(0), dec!(0)));
+}
+
+#[test]
+fn stop_orders1() {
+    let mut pie_ob = PieOrderBook::new(dec!(10), 2).unwrap();
+
+    let stop = |outcome, side, trigger, trigger_price, limit_price| StopOrder {
+        outcome,
+        side,
+        quantity: dec!(1),
+        trigger,
+        trigger_price,
+        limit_price,
+        options: OrderOptions::default(),
+    };
+
+    pie_ob
+        .process_limit_order(1, 0, Side::Sell, dec!(5), dec!(2))
+        .unwrap();
+
+    use rust_pie_ob::errors::SubmitStopOrder as E;
+    assert_eq!(
+        pie_ob.submit_stop_order(1, stop(0, Side::Buy, StopTrigger::LastTrade, dec!(5), None)),
+        Err(E::DuplicateOrderId)
+    );
+    assert_eq!(
+        pie_ob.submit_stop_order(
+            10,
+            stop(
+                0,
+                Side::Buy,
+                StopTrigger::LastTrade,
+                dec!(5),
+                Some(dec!(10))
+            )
+        ),
+        Err(E::OrderValidationFailed)
+    );
+
+    // buy stop limit triggered by the last trade price
+    pie_ob
+        .submit_stop_order(
+            10,
+            stop(0, Side::Buy, StopTrigger::LastTrade, dec!(5), Some(dec!(6))),
+        )
+        .unwrap();
+    // sell stop market triggered by the effective best bid
+    pie_ob
+        .submit_stop_order(11, stop(1, Side::Sell, StopTrigger::Quote, dec!(3), None))
+        .unwrap();
+    assert_eq!(pie_ob.get_last_trade_price(0), None);
+    assert!(pie_ob.get_stop_order(10).is_some());
+
+    pie_ob
+        .process_limit_order(2, 0, Side::Buy, dec!(5), dec!(1))
+        .unwrap();
+    assert_eq!(pie_ob.get_last_trade_price(0), Some(dec!(5)));
+    assert!(pie_ob.get_stop_order(10).is_none());
+    assert!(pie_ob.get_order(1).is_none());
+    assert_eq!(pie_ob.get_position(10).unwrap().quantity, dec!(1));
+
+    // no bid on outcome 1 yet, the implied bid left with the outcome 0 asks
+    assert!(pie_ob.get_stop_order(11).is_some());
+    pie_ob
+        .process_limit_order(3, 1, Side::Buy, dec!(2), dec!(1))
+        .unwrap();
+    assert!(pie_ob.get_stop_order(11).is_none());
+    assert_eq!(pie_ob.get_position(11).unwrap().quantity, dec!(-1));
+    assert_eq!(pie_ob.get_last_trade_price(1), Some(dec!(2)));
+
+    // triggered orders are not logged, replaying triggers them again
+    pie_ob
+        .submit_stop_order(
+            12,
+            stop(1, Side::Buy, StopTrigger::LastTrade, dec!(8), None),
+        )
+        .unwrap();
+    pie_ob.cancel_stop_order(12).unwrap();
+    use rust_pie_ob::errors::CancelOrder;
+    assert_eq!(pie_ob.cancel_stop_order(12), Err(CancelOrder::UnknownOrder));
+    assert_eq!(pie_ob.command_log().len(), 8);
+
+    let replayed = PieOrderBook::replay(pie_ob.command_log().to_vec()).unwrap();
+    assert_eq!(replayed.to_string(), pie_ob.to_string());
+    assert_eq!(replayed.get_position(11).unwrap().quantity, dec!(-1));
+    assert_eq!(replayed.get_last_trade_price(1), Some(dec!(2)));
+}
+
+#[test]
+fn stop_orders2() {
+    let mut pie_ob = PieOrderBook::new(dec!(10), 2).unwrap();
+
+    let stop = |outcome, side, limit_price| StopOrder {
+        outcome,
+        side,
+        quantity: dec!(1),
+        trigger: StopTrigger::Quote,
+        trigger_price: dec!(5),
+        limit_price,
+        options: OrderOptions::default(),
+    };
+
+    pie_ob
+        .process_limit_order(1, 0, Side::Sell, dec!(6), dec!(1))
+        .unwrap();
+    pie_ob.halt_outcome(0).unwrap();
+    pie_ob
+        .submit_stop_order(10, stop(0, Side::Buy, Some(dec!(6))))
+        .unwrap();
+    pie_ob
+        .submit_stop_order(11, stop(1, Side::Sell, None))
+        .unwrap();
+    assert!(pie_ob.get_stop_order(10).is_some());
+    assert!(pie_ob.get_stop_order(11).is_some());
+
+    // both trigger once outcome 0 resumes, the first submitted one first
+    pie_ob.resume_outcome(0).unwrap();
+    assert!(pie_ob.get_stop_order(10).is_none());
+    assert_eq!(pie_ob.get_position(10).unwrap().quantity, dec!(1));
+    assert!(pie_ob.get_order(1).is_none());
+    assert!(pie_ob.get_stop_order(11).is_some());
+}
+
+#[test]
+fn good_till_time1() {
+    let mut pie_ob = PieOrderBook::new(dec!(10), 2).unwrap();
+    let clock = ManualClock::new(1000);
+    pie_ob.set_clock(clock.clone());
+
+    let good_till = |time_in_force| OrderOptions {
+        time_in_force,
+        ..OrderOptions::default()
+    };
+
+    pie_ob
+        .process_limit_order_with_options(
+            1,
+            0,
+            Side::Sell,
+            dec!(6),
+            dec!(1),
+            good_till(TimeInForce::GoodTillTime(1500)),
+        )
+        .unwrap();
+    pie_ob
+        .process_limit_order(2, 0, Side::Sell, dec!(7), dec!(1))
+        .unwrap();
+    pie_ob
+        .process_limit_order_with_options(
+            3,
+            1,
+            Side::Buy,
+            dec!(2),
+            dec!(1),
+            good_till(TimeInForce::GoodTillTime(2000)),
+        )
+        .unwrap();
+    pie_ob
+        .submit_stop_order(
+            10,
+            StopOrder {
+                outcome: 1,
+                side: Side::Buy,
+                quantity: dec!(1),
+                trigger: StopTrigger::LastTrade,
+                trigger_price: dec!(5),
+                limit_price: None,
+                options: good_till(TimeInForce::GoodTillTime(1200)),
+            },
+        )
+        .unwrap();
+    assert_eq!(pie_ob.get_order(1).unwrap().expires_at, Some(1500));
+
+    // nothing expired yet
+    assert_eq!(pie_ob.expire_due_orders(), Ok(vec![]));
+
+    clock.advance(500);
+    assert_eq!(pie_ob.now(), 1500);
+    assert_eq!(pie_ob.expire_due_orders(), Ok(vec![1, 10]));
+    assert!(pie_ob.get_order(1).is_none());
+    assert!(pie_ob.get_order(2).is_some());
+    assert!(pie_ob.get_stop_order(10).is_none());
+    assert_eq!(pie_ob.get_expired_until(), Some(1500));
+
+    // orders can not expire in the past
+    use rust_pie_ob::errors::ProcessLimitOrder as E;
+    assert_eq!(
+        pie_ob
+            .process_limit_order_with_options(
+                4,
+                0,
+                Side::Sell,
+                dec!(6),
+                dec!(1),
+                good_till(TimeInForce::GoodTillTime(1500)),
+            )
+            .err(),
+        Some(E::OrderValidationFailed)
+    );
+
+    // expiry sweeps are replayed at their logged time
+    let mut replayed = PieOrderBook::replay(pie_ob.command_log().to_vec()).unwrap();
+    assert_eq!(replayed.to_string(), pie_ob.to_string());
+    assert_eq!(
+        replayed.apply(Command::ExpireOrders { now: 2000 }),
+        Ok(CommandOutput::Expired(vec![3]))
+    );
+    assert!(replayed.get_order(3).is_none());
+    assert!(replayed.get_order(2).is_some());
+
+    // orders are validated against the clock, without an expiry sweep
+    clock.set(1700);
+    assert_eq!(
+        pie_ob
+            .process_limit_order_with_options(
+                5,
+                0,
+                Side::Sell,
+                dec!(6),
+                dec!(1),
+                good_till(TimeInForce::GoodTillTime(1600)),
+            )
+            .err(),
+        Some(E::OrderValidationFailed)
+    );
+
+    // expired orders are expired before matching
+    pie_ob.cancel_order(2).unwrap();
+    clock.set(2500);
+    let res = pie_ob
+        .process_limit_order(6, 1, Side::Sell, dec!(2), dec!(1))
+        .unwrap();
+    assert!(res.is_empty());
+    assert!(pie_ob.get_order(3).is_none());
+    assert!(pie_ob.get_order(6).is_some());
+    assert_eq!(pie_ob.get_expired_until(), Some(2500));
+    let log = pie_ob.command_log();
+    assert!(matches!(
+        log[log.len() - 2..],
+        [
+            Command::ExpireOrders { now: 2500 },
+            Command::LimitOrder {
+                id: 6,
+                now: 2500,
+                ..
+            }
+        ]
+    ));
+
+    // replay does not depend on the clock
+    let replayed = PieOrderBook::replay(pie_ob.command_log().to_vec()).unwrap();
+    assert_eq!(replayed.to_string(), pie_ob.to_string());
+}
+
+#[test]
+fn iceberg_orders1() {
+    let mut pie_ob = PieOrderBook::new(dec!(10), 2).unwrap();
+
+    let iceberg = OrderOptions {
+        display_quantity: Some(dec!(2)),
+        ..Default::default()
+    };
+    pie_ob
+        .process_limit_order_with_options(1, 0, Side::Sell, dec!(6), dec!(5), iceberg)
+        .unwrap();
+    pie_ob
+        .process_limit_order(2, 0, Side::Sell, dec!(6), dec!(1))
+        .unwrap();
+
+    // only the peak is displayed
+    let order = pie_ob.get_order(1).unwrap();
+    assert_eq!((order.quantity, order.hidden), (dec!(2), dec!(3)));
+    assert_eq!(
+        pie_ob.depth(0, 1).unwrap().asks,
+        vec![PriceLevel {
+            price: dec!(6),
+            quantity: dec!(3)
+        }]
+    );
+
+    // the replenished peak loses time priority to order 2
+    let res = pie_ob
+        .process_limit_order_with_options(3, 0, Side::Buy, dec!(6), dec!(3), Default::default())
+        .unwrap();
+    assert_eq!(
+        res.fills
+            .iter()
+            .map(|fill| (fill.maker, fill.quantity))
+            .collect::<Vec<_>>(),
+        vec![(1, dec!(2)), (2, dec!(1))]
+    );
+    let order = pie_ob.get_order(1).unwrap();
+    assert_eq!((order.quantity, order.hidden), (dec!(2), dec!(1)));
+
+    // replenishes while merging through the other outcome order books
+    let res = pie_ob
+        .process_limit_order_with_options(4, 1, Side::Sell, dec!(4), dec!(4), Default::default())
+        .unwrap();
+    assert_eq!(
+        res.fills
+            .iter()
+            .map(|fill| (fill.maker, fill.quantity, fill.match_type))
+            .collect::<Vec<_>>(),
+        vec![
+            (1, dec!(2), MatchType::Merge),
+            (1, dec!(1), MatchType::Merge)
+        ]
+    );
+    assert_eq!(res.unfilled, dec!(1));
+    assert!(pie_ob.get_order(1).is_none());
+    assert_eq!(pie_ob.get_position(1).unwrap().quantity, dec!(-5));
+
+    use rust_pie_ob::errors::ProcessLimitOrder as E;
+    assert_eq!(
+        pie_ob
+            .process_limit_order_with_options(
+                5,
+                0,
+                Side::Sell,
+                dec!(6),
+                dec!(5),
+                OrderOptions {
+                    display_quantity: Some(dec!(0)),
+                    ..Default::default()
+                },
+            )
+            .err(),
+        Some(E::OrderValidationFailed)
+    );
+    // fill or kill orders count the hidden quantity
+    let mut pie_ob = PieOrderBook::new(dec!(10), 2).unwrap();
+    pie_ob
+        .process_limit_order_with_options(
+            1,
+            0,
+            Side::Sell,
+            dec!(4),
+            dec!(10),
+            OrderOptions {
+                display_quantity: Some(dec!(2)),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    let res = pie_ob
+        .process_limit_order_with_options(
+            2,
+            0,
+            Side::Buy,
+            dec!(5),
+            dec!(5),
+            OrderOptions {
+                time_in_force: TimeInForce::FillOrKill,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    assert_eq!(res.unfilled, dec!(0));
+    assert_eq!(pie_ob.get_position(2).unwrap().quantity, dec!(5));
+}
+
+#[test]
+fn complete_sets1() {
+    let mut pie_ob = PieOrderBook::<u32, u32>::with_accounts(dec!(10), 3).unwrap();
+    pie_ob.deposit(1, dec!(50)).unwrap();
+
+    pie_ob.mint_complete_set(1, dec!(3)).unwrap();
+    let minted = pie_ob.get_account(1).unwrap();
+    assert_eq!(minted.cash, dec!(20));
+    assert_eq!(minted.positions, vec![dec!(3); 3]);
+    assert_eq!(minted.sets, dec!(3));
+
+    use rust_pie_ob::errors::{MergeCompleteSet, MintCompleteSet};
+    assert_eq!(
+        pie_ob.mint_complete_set(1, dec!(3)),
+        Err(MintCompleteSet::InsufficientFunds)
+    );
+    assert_eq!(
+        pie_ob.mint_complete_set(2, dec!(1)),
+        Err(MintCompleteSet::UnknownAccount)
+    );
+
+    // minted contracts cover sell orders, reserved contracts can not be merged
+    pie_ob
+        .process_limit_order_with_options(1, 0, Side::Sell, dec!(4), dec!(2), account(1))
+        .unwrap();
+    assert_eq!(pie_ob.get_account(1).unwrap().locked, dec!(0));
+    assert_eq!(
+        pie_ob.merge_complete_set(1, dec!(2)),
+        Err(MergeCompleteSet::InsufficientContracts)
+    );
+    pie_ob.merge_complete_set(1, dec!(1)).unwrap();
+    let merged = pie_ob.get_account(1).unwrap();
+    assert_eq!(merged.cash, dec!(30));
+    assert_eq!(merged.positions, vec![dec!(2); 3]);
+
+    let replayed = PieOrderBook::replay(pie_ob.command_log().to_vec()).unwrap();
+    assert_eq!(replayed.get_account(1), pie_ob.get_account(1));
+
+    // every set pays out the contract price
+    let settlement = pie_ob.resolve(1).unwrap();
+    assert_eq!(
+        settlement.account_payouts,
+        vec![AccountPayout {
+            account: 1,
+            amount: dec!(20)
+        }]
+    );
+    assert_eq!(pie_ob.get_account(1).unwrap().cash, dec!(50));
+}
+
+#[test]
+fn basket_order1() {
+    let mut pie_ob = PieOrderBook::new(dec!(10), 3).unwrap();
+
+    pie_ob
+        .process_limit_order(1, 0, Side::Sell, dec!(3), dec!(2))
+        .unwrap();
+    pie_ob
+        .process_limit_order(2, 1, Side::Sell, dec!(4), dec!(1))
+        .unwrap();
+
+    use rust_pie_ob::errors::ProcessBasketOrder as E;
+    assert_eq!(
+        pie_ob.process_basket_order(
+            vec![(10, 0), (11, 1), (12, 2)],
+            Side::Buy,
+            dec!(7),
+            dec!(2),
+            Default::default()
+        ),
+        Err(E::OrderValidationFailed)
+    );
+    assert_eq!(
+        pie_ob.process_basket_order(
+            vec![(10, 0), (10, 1)],
+            Side::Buy,
+            dec!(7),
+            dec!(2),
+            Default::default()
+        ),
+        Err(E::DuplicateOrderId)
+    );
+
+    // outcome 1 can not be filled completely, so nothing is matched
+    let res = pie_ob
+        .process_basket_order(
+            vec![(10, 0), (11, 1)],
+            Side::Buy,
+            dec!(7),
+            dec!(2),
+            Default::default(),
+        )
+        .unwrap();
+    assert!(res
+        .iter()
+        .all(|execution| execution.matches.is_empty() && execution.unfilled == dec!(2)));
+    assert_eq!(pie_ob.get_order(1).unwrap().quantity, dec!(2));
+
+    pie_ob
+        .process_limit_order(3, 1, Side::Sell, dec!(4), dec!(1))
+        .unwrap();
+
+    // costs 14 for 2 baskets
+    let res = pie_ob
+        .process_basket_order(
+            vec![(10, 0), (11, 1)],
+            Side::Buy,
+            dec!(6.5),
+            dec!(2),
+            Default::default(),
+        )
+        .unwrap();
+    assert!(res.iter().all(|execution| execution.matches.is_empty()));
+
+    let res = pie_ob
+        .process_basket_order(
+            vec![(12, 0), (13, 1)],
+            Side::Buy,
+            dec!(7),
+            dec!(2),
+            Default::default(),
+        )
+        .unwrap();
+    assert_eq!(
+        res.iter()
+            .map(|execution| (execution.unfilled, execution.fills.len()))
+            .collect::<Vec<_>>(),
+        vec![(dec!(0), 1), (dec!(0), 2)]
+    );
+    assert_eq!(pie_ob.get_position(12).unwrap().cost, dec!(6));
+    assert_eq!(pie_ob.get_position(13).unwrap().cost, dec!(8));
+    assert!(pie_ob.get_order(1).is_none());
+
+    let replayed = PieOrderBook::replay(pie_ob.command_log().to_vec()).unwrap();
+    assert_eq!(replayed.to_string(), pie_ob.to_string());
+    assert_eq!(replayed.get_position(13).unwrap().cost, dec!(8));
+
+    // baskets mint complete sets with the bids of the outcomes not in the basket
+    let mut pie_ob = PieOrderBook::new(dec!(10), 3).unwrap();
+    pie_ob
+        .process_limit_order(1, 0, Side::Buy, dec!(4), dec!(5))
+        .unwrap();
+    let res = pie_ob
+        .process_basket_order(
+            vec![(2, 1), (3, 2)],
+            Side::Buy,
+            dec!(6),
+            dec!(5),
+            Default::default(),
+        )
+        .unwrap();
+    assert!(res.iter().all(|execution| execution.unfilled.is_zero()));
+    assert_eq!(
+        res[0]
+            .fills
+            .iter()
+            .map(|fill| (fill.maker, fill.quantity, fill.match_type))
+            .collect::<Vec<_>>(),
+        vec![(1, dec!(5), MatchType::Mint)]
+    );
+    assert_eq!(pie_ob.get_position(1).unwrap().cost, dec!(20));
+    assert_eq!(pie_ob.get_position(2).unwrap().cost, dec!(15));
+    assert_eq!(pie_ob.get_position(3).unwrap().cost, dec!(15));
+    assert!(pie_ob.get_order(1).is_none());
+
+    // and merge complete sets with their asks
+    pie_ob
+        .process_limit_order(4, 0, Side::Sell, dec!(3), dec!(2))
+        .unwrap();
+    let res = pie_ob
+        .process_basket_order(
+            vec![(5, 1), (6, 2)],
+            Side::Sell,
+            dec!(6),
+            dec!(2),
+            Default::default(),
+        )
+        .unwrap();
+    assert!(res.iter().all(|execution| execution.unfilled.is_zero()));
+    assert_eq!(pie_ob.get_position(4).unwrap().quantity, dec!(-2));
+    assert_eq!(pie_ob.get_position(5).unwrap().cost, dec!(-7));
+    assert_eq!(pie_ob.get_position(6).unwrap().quantity, dec!(-2));
+}
+
+#[test]
+fn market_state1() {
+    let mut pie_ob = PieOrderBook::new(dec!(10), 3).unwrap();
+    let limit_order = |pie_ob: &mut PieOrderBook<u32>, id, outcome, side, price| {
+        pie_ob.process_limit_order_with_options(
+            id,
+            outcome,
+            side,
+            price,
+            dec!(1),
+            Default::default(),
+        )
+    };
+
+    // orders are queued before the market opens
+    pie_ob.set_market_state(MarketState::PreOpen).unwrap();
+    let res = limit_order(&mut pie_ob, 1, 0, Side::Sell, dec!(4)).unwrap();
+    assert!(res.matches.is_empty());
+    assert!(pie_ob.get_order(1).is_none());
+    limit_order(&mut pie_ob, 2, 0, Side::Buy, dec!(5)).unwrap();
+
+    use rust_pie_ob::errors::{ProcessMarketOrder, SetMarketState};
+    assert_eq!(
+        pie_ob.process_market_order(3, 0, Side::Buy, dec!(1), None),
+        Err(ProcessMarketOrder::MarketNotOpen)
+    );
+
+    pie_ob.set_market_state(MarketState::Open).unwrap();
+    assert_eq!(pie_ob.get_position(2).unwrap().quantity, dec!(1));
+    assert_eq!(
+        pie_ob.set_market_state(MarketState::PreOpen),
+        Err(SetMarketState::InvalidTransition)
+    );
+    assert_eq!(
+        pie_ob.set_market_state(MarketState::Open),
+        Err(SetMarketState::InvalidTransition)
+    );
+
+    // a halted outcome is not matched through
+    limit_order(&mut pie_ob, 4, 1, Side::Buy, dec!(3)).unwrap();
+    limit_order(&mut pie_ob, 5, 2, Side::Buy, dec!(3)).unwrap();
+    pie_ob.halt_outcome(2).unwrap();
+    assert!(pie_ob.is_outcome_halted(2));
+    assert_eq!(pie_ob.quote(0).unwrap().implied_ask, None);
+    let res = limit_order(&mut pie_ob, 6, 0, Side::Buy, dec!(4)).unwrap();
+    assert!(res.matches.is_empty());
+    assert_eq!(pie_ob.get_order(6).unwrap().quantity, dec!(1));
+    limit_order(&mut pie_ob, 9, 1, Side::Sell, dec!(4)).unwrap();
+    let res = pie_ob
+        .process_limit_order_with_options(
+            10,
+            1,
+            Side::Buy,
+            dec!(5),
+            dec!(2),
+            OrderOptions {
+                time_in_force: TimeInForce::FillOrKill,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    assert!(res.matches.is_empty());
+    assert_eq!(res.unfilled, dec!(2));
+    pie_ob.cancel_order(9).unwrap();
+
+    // orders of the halted outcome are queued until it resumes
+    limit_order(&mut pie_ob, 7, 2, Side::Sell, dec!(3)).unwrap();
+    assert!(pie_ob.get_order(5).is_some());
+    pie_ob.resume_outcome(2).unwrap();
+    assert!(pie_ob.get_order(5).is_none());
+    assert_eq!(pie_ob.get_position(7).unwrap().quantity, dec!(-1));
+
+    pie_ob.set_market_state(MarketState::Closed).unwrap();
+    use rust_pie_ob::errors::ProcessLimitOrder as E;
+    assert_eq!(
+        limit_order(&mut pie_ob, 8, 0, Side::Sell, dec!(4)).err(),
+        Some(E::MarketNotOpen)
+    );
+    assert_eq!(
+        pie_ob.set_market_state(MarketState::Resolved),
+        Err(SetMarketState::InvalidState)
+    );
+    assert_eq!(
+        pie_ob.set_market_state(MarketState::Open),
+        Err(SetMarketState::InvalidTransition)
+    );
+
+    let replayed = PieOrderBook::replay(pie_ob.command_log().to_vec()).unwrap();
+    assert_eq!(replayed.to_string(), pie_ob.to_string());
+    assert_eq!(replayed.get_market_state(), MarketState::Closed);
+
+    pie_ob.void().unwrap();
+    assert_eq!(pie_ob.get_market_state(), MarketState::Voided);
+}
+
+#[test]
+fn market_state2() {
+    let mut pie_ob: PieOrderBook<u32, u32> = PieOrderBook::with_accounts(dec!(10), 2).unwrap();
+    pie_ob.deposit(1, dec!(10)).unwrap();
+    pie_ob.set_market_state(MarketState::PreOpen).unwrap();
+
+    // queued orders lock collateral like resting orders
+    pie_ob
+        .process_limit_order_with_options(1, 0, Side::Buy, dec!(4), dec!(2), account(1))
+        .unwrap();
+    assert_eq!(pie_ob.get_account(1).unwrap().locked, dec!(8));
+    use rust_pie_ob::errors::ProcessLimitOrder as E;
+    assert_eq!(
+        pie_ob
+            .process_limit_order_with_options(2, 1, Side::Buy, dec!(4), dec!(1), account(1))
+            .err(),
+        Some(E::InsufficientCollateral)
+    );
+
+    pie_ob.cancel_order(1).unwrap();
+    assert_eq!(pie_ob.get_account(1).unwrap().locked, dec!(0));
+
+    // released orders keep their collateral locked once
+    pie_ob
+        .process_limit_order_with_options(3, 0, Side::Buy, dec!(4), dec!(2), account(1))
+        .unwrap();
+    pie_ob.set_market_state(MarketState::Open).unwrap();
+    assert!(pie_ob.get_order(3).is_some());
+    assert_eq!(pie_ob.get_account(1).unwrap().locked, dec!(8));
+
+    // halted orders can be reduced, but not repriced
+    pie_ob.set_market_state(MarketState::Halted).unwrap();
+    pie_ob.amend_order(3, dec!(4), dec!(1)).unwrap();
+    assert_eq!(pie_ob.get_order(3).unwrap().quantity, dec!(1));
+    assert_eq!(pie_ob.get_account(1).unwrap().locked, dec!(4));
+    use rust_pie_ob::errors::AmendOrder;
+    assert_eq!(
+        pie_ob.amend_order(3, dec!(3), dec!(1)).err(),
+        Some(AmendOrder::MarketNotOpen)
+    );
+    assert_eq!(
+        pie_ob.amend_order(3, dec!(4), dec!(2)).err(),
+        Some(AmendOrder::MarketNotOpen)
+    );
+}